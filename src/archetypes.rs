@@ -0,0 +1,310 @@
+use bevy::{asset::LoadState, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    config::{ConfigError, RonAsset, RonAssetLoader},
+    patterns::BulletPattern,
+    waves::EnemyKind,
+};
+
+// path of the enemy definitions, relative to the assets folder
+const ARCHETYPES_PATH: &str = "enemies.ron";
+
+// How an enemy flies after appearing behind the right edge
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum MovementPattern {
+    // straight to the left
+    Straight,
+    // to the left while weaving up and down, `amplitude` in units and `frequency` in
+    // waves per second
+    Sine { amplitude: f32, frequency: f32 },
+    // dives at the player, turning by at most `turn_rate` radians per second, and
+    // flies on straight once it has passed them
+    Homing { turn_rate: f32 },
+    // flies in until `stop_x` (a fraction of the half width, measured from the center)
+    // and stays there
+    Turret { stop_x: f32 },
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Archetype {
+    // image in the assets folder
+    pub sprite: String,
+    // hex color the sprite is multiplied with, e.g. "#ff8080"
+    pub tint: String,
+    pub hp: f32,
+    // units per second
+    pub speed: f32,
+    // points for destroying it, before the combo multiplier
+    pub points: u32,
+    pub movement: MovementPattern,
+    // `None` never shoots, a weapon is only used by ships the wave lets shoot
+    pub weapon: Option<BulletPattern>,
+    // damage absorbed before the hp, see `health::Shield`
+    #[serde(default)]
+    pub shield: f32,
+    // shield recharged per second
+    #[serde(default)]
+    pub shield_regen: f32,
+}
+
+impl Archetype {
+    pub fn color(&self) -> Color {
+        Color::hex(&self.tint).unwrap_or(Color::WHITE)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: String| Err(ConfigError::Invalid { field, reason });
+
+        if Color::hex(&self.tint).is_err() {
+            return invalid("tint", format!("`{}` is not a hex color", self.tint));
+        }
+        for (field, value) in [("hp", self.hp), ("speed", self.speed)] {
+            if !(value.is_finite() && value > 0.) {
+                return invalid(field, format!("must be a positive number, got {}", value));
+            }
+        }
+        for (field, value) in [("shield", self.shield), ("shield_regen", self.shield_regen)] {
+            if !(value.is_finite() && value >= 0.) {
+                return invalid(field, format!("must not be negative, got {}", value));
+            }
+        }
+
+        match self.movement {
+            MovementPattern::Straight => (),
+            MovementPattern::Sine {
+                amplitude,
+                frequency,
+            } => {
+                if !(amplitude.is_finite() && frequency.is_finite() && frequency >= 0.) {
+                    return invalid("frequency", "must not be negative".into());
+                }
+            }
+            MovementPattern::Homing { turn_rate } => {
+                if !(turn_rate.is_finite() && turn_rate >= 0.) {
+                    return invalid("turn_rate", "must not be negative".into());
+                }
+            }
+            MovementPattern::Turret { stop_x } => {
+                if !(-1. ..=1.).contains(&stop_x) {
+                    return invalid("stop_x", "must be between -1 and 1".into());
+                }
+            }
+        }
+        if let Some(weapon) = &self.weapon {
+            weapon.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+// Stats and behavior of every `EnemyKind`
+//
+// Loaded from `assets/enemies.ron`, a kind missing from the file keeps the default
+// below. Changes apply to enemies spawned after the file is reloaded.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnemyArchetypes {
+    pub rocket: Archetype,
+    pub sine: Archetype,
+    pub kamikaze: Archetype,
+    pub turret: Archetype,
+    pub shielded: Archetype,
+}
+
+impl Default for EnemyArchetypes {
+    fn default() -> Self {
+        let rocket = Archetype {
+            sprite: "Rocket.png".into(),
+            tint: "#ffffff".into(),
+            hp: 3.,
+            speed: 210.,
+            points: 100,
+            movement: MovementPattern::Straight,
+            weapon: Some(BulletPattern::Forward {
+                speed: 300.,
+                cooldown: 3.5,
+            }),
+            shield: 0.,
+            shield_regen: 0.,
+        };
+
+        Self {
+            sine: Archetype {
+                tint: "#80ff80".into(),
+                hp: 2.,
+                speed: 180.,
+                points: 150,
+                movement: MovementPattern::Sine {
+                    amplitude: 150.,
+                    frequency: 0.5,
+                },
+                weapon: Some(BulletPattern::Aimed {
+                    speed: 300.,
+                    cooldown: 3.,
+                }),
+                ..rocket.clone()
+            },
+            kamikaze: Archetype {
+                tint: "#ff6060".into(),
+                hp: 1.,
+                speed: 380.,
+                points: 200,
+                movement: MovementPattern::Homing { turn_rate: 1.5 },
+                weapon: None,
+                ..rocket.clone()
+            },
+            turret: Archetype {
+                tint: "#ffd040".into(),
+                hp: 5.,
+                speed: 150.,
+                points: 250,
+                movement: MovementPattern::Turret { stop_x: 0.6 },
+                weapon: Some(BulletPattern::AimedBurst {
+                    shots: 3,
+                    interval: 0.15,
+                    speed: 350.,
+                    cooldown: 3.,
+                }),
+                ..rocket.clone()
+            },
+            shielded: Archetype {
+                tint: "#60a0ff".into(),
+                hp: 4.,
+                speed: 120.,
+                points: 300,
+                weapon: Some(BulletPattern::Ring {
+                    count: 8,
+                    speed: 200.,
+                    cooldown: 4.,
+                }),
+                shield: 4.,
+                shield_regen: 1.,
+                ..rocket.clone()
+            },
+            rocket,
+        }
+    }
+}
+
+impl EnemyArchetypes {
+    pub fn get(&self, kind: EnemyKind) -> &Archetype {
+        match kind {
+            EnemyKind::Rocket => &self.rocket,
+            EnemyKind::Sine => &self.sine,
+            EnemyKind::Kamikaze => &self.kamikaze,
+            EnemyKind::Turret => &self.turret,
+            EnemyKind::Shielded => &self.shielded,
+        }
+    }
+}
+
+impl RonAsset for EnemyArchetypes {
+    const EXTENSION: &'static str = "enemies.ron";
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        for archetype in [
+            &self.rocket,
+            &self.sine,
+            &self.kamikaze,
+            &self.turret,
+            &self.shielded,
+        ] {
+            archetype.validate()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+pub struct EnemyArchetypesHandle(Handle<EnemyArchetypes>);
+
+pub struct ArchetypePlugin;
+
+impl Plugin for ArchetypePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EnemyArchetypes>()
+            .init_asset_loader::<RonAssetLoader<EnemyArchetypes>>()
+            .init_resource::<EnemyArchetypes>()
+            .add_systems(Startup, load_archetypes)
+            .add_systems(Update, apply_archetypes);
+    }
+}
+
+fn load_archetypes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(EnemyArchetypesHandle(asset_server.load(ARCHETYPES_PATH)));
+}
+
+fn apply_archetypes(
+    mut events: EventReader<AssetEvent<EnemyArchetypes>>,
+    handle: Res<EnemyArchetypesHandle>,
+    loaded: Res<Assets<EnemyArchetypes>>,
+    mut archetypes: ResMut<EnemyArchetypes>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } if *id == handle.0.id() => {
+                if let Some(definitions) = loaded.get(*id) {
+                    info!("applied enemy definitions from assets/{}", ARCHETYPES_PATH);
+                    *archetypes = definitions.clone();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// same as `config::config_settled`, for the enemy definitions
+pub fn archetypes_settled(
+    handle: Option<Res<EnemyArchetypesHandle>>,
+    asset_server: Res<AssetServer>,
+    loaded: Res<Assets<EnemyArchetypes>>,
+    archetypes: Res<EnemyArchetypes>,
+) -> bool {
+    let Some(handle) = handle else {
+        return false;
+    };
+    match asset_server.load_state(&handle.0) {
+        LoadState::Loaded => loaded.get(&handle.0) == Some(&*archetypes),
+        LoadState::Failed => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_archetypes_match_defaults() {
+        let archetypes =
+            EnemyArchetypes::from_bytes(include_bytes!("../assets/enemies.ron")).unwrap();
+
+        assert_eq!(archetypes, EnemyArchetypes::default());
+    }
+
+    #[test]
+    fn invalid_tint_is_rejected() {
+        let err = EnemyArchetypes::from_bytes(
+            br#"(
+                rocket: (
+                    sprite: "Rocket.png",
+                    tint: "reddish",
+                    hp: 1.,
+                    speed: 100.,
+                    points: 10,
+                    movement: Straight,
+                    weapon: None,
+                ),
+            )"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "invalid value for `tint`: `reddish` is not a hex color"
+        );
+    }
+}
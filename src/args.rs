@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+// Command line arguments of the game
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    // `--seed <n>`, seed of every round
+    pub seed: Option<u64>,
+    // `--record <file>`, saves the input of every round to the file
+    pub record: Option<PathBuf>,
+    // `--replay <file>`, plays a recorded round back instead of reading the keyboard
+    pub replay: Option<PathBuf>,
+}
+
+impl Args {
+    // parses the arguments without the program name
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--seed" => {
+                    let value = value()?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| format!("--seed must be a number, got {}", value))?;
+                    parsed.seed = Some(seed);
+                }
+                "--record" => parsed.record = Some(value()?.into()),
+                "--replay" => parsed.replay = Some(value()?.into()),
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+
+        if parsed.record.is_some() && parsed.replay.is_some() {
+            return Err("--record and --replay can't be used together".into());
+        }
+
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn arguments_are_parsed() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+        assert_eq!(
+            parse(&["--seed", "42", "--record", "run.replay"]),
+            Ok(Args {
+                seed: Some(42),
+                record: Some("run.replay".into()),
+                replay: None,
+            })
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_reported() {
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--seed", "abc"]).is_err());
+        assert!(parse(&["--fast"]).is_err());
+        assert!(parse(&["--record", "a", "--replay", "b"]).is_err());
+    }
+}
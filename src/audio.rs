@@ -0,0 +1,226 @@
+use bevy::{
+    audio::{GlobalVolume, Volume},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{
+    health::{DamageEvent, DeathEvent, Invulnerable},
+    player::{LifeLost, Player},
+    powerups::PowerUpCollected,
+    projectile::ShotFired,
+    settings::UserSettings,
+    waves::WaveEnemy,
+    TickSet,
+};
+
+// most copies of the same sound playing at once, more are dropped
+const MAX_INSTANCES: usize = 4;
+
+// Sound effects of the game, several of them share a file played at another speed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    PlayerShot,
+    EnemyShot,
+    EnemyHit,
+    EnemyDestroyed,
+    PlayerHit,
+    LifeLost,
+    PowerUp,
+}
+
+impl Sfx {
+    const ALL: [Sfx; 7] = [
+        Sfx::PlayerShot,
+        Sfx::EnemyShot,
+        Sfx::EnemyHit,
+        Sfx::EnemyDestroyed,
+        Sfx::PlayerHit,
+        Sfx::LifeLost,
+        Sfx::PowerUp,
+    ];
+
+    fn path(self) -> &'static str {
+        match self {
+            Sfx::PlayerShot | Sfx::PowerUp => "sounds/shoot_player.wav",
+            Sfx::EnemyShot => "sounds/shoot_rocket.wav",
+            Sfx::EnemyHit | Sfx::EnemyDestroyed | Sfx::PlayerHit | Sfx::LifeLost => {
+                "sounds/death_rocket.wav"
+            }
+        }
+    }
+
+    // volume before the settings are applied, and playback speed
+    fn playback(self) -> (f32, f32) {
+        match self {
+            Sfx::PlayerShot => (0.5, 1.),
+            Sfx::EnemyShot => (0.3, 1.),
+            Sfx::EnemyHit => (0.3, 2.),
+            Sfx::EnemyDestroyed => (0.6, 1.),
+            Sfx::PlayerHit => (0.7, 0.8),
+            Sfx::LifeLost => (1., 0.5),
+            Sfx::PowerUp => (0.6, 1.6),
+        }
+    }
+}
+
+// Asks for a sound effect to be played
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlaySfx(pub Sfx);
+
+// Handles of every sound, loaded once at startup
+#[derive(Resource)]
+struct SfxHandles(HashMap<Sfx, Handle<AudioSource>>);
+
+// Marks a playing sound effect, despawned by bevy once it's over
+#[derive(Component)]
+struct PlayingSfx(Sfx);
+
+// Plays sound effects for what happens in the game, with the volumes of `UserSettings`
+//
+// Nothing is played without bevy's `AudioPlugin`, e.g. in headless runs.
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>()
+            .add_systems(Startup, load_sounds.run_if(resource_exists::<GlobalVolume>))
+            .add_systems(
+                FixedUpdate,
+                (sounds_for_shots_and_pickups, sounds_for_hits).in_set(TickSet::Cleanup),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_master_volume.run_if(resource_changed::<UserSettings>),
+                    play_sounds,
+                )
+                    .run_if(resource_exists::<GlobalVolume>),
+            );
+    }
+}
+
+fn load_sounds(asset_server: Res<AssetServer>, mut commands: Commands) {
+    let handles = Sfx::ALL
+        .into_iter()
+        .map(|sfx| (sfx, asset_server.load(sfx.path())))
+        .collect();
+    commands.insert_resource(SfxHandles(handles));
+}
+
+fn sounds_for_shots_and_pickups(
+    mut shots: EventReader<ShotFired>,
+    mut powerups: EventReader<PowerUpCollected>,
+    mut sounds: EventWriter<PlaySfx>,
+) {
+    for shot in shots.read() {
+        sounds.send(PlaySfx(if shot.by_player {
+            Sfx::PlayerShot
+        } else {
+            Sfx::EnemyShot
+        }));
+    }
+    for _ in powerups.read() {
+        sounds.send(PlaySfx(Sfx::PowerUp));
+    }
+}
+
+// runs before the commands of the tick despawn what died
+fn sounds_for_hits(
+    mut damage: EventReader<DamageEvent>,
+    mut deaths: EventReader<DeathEvent>,
+    mut lives: EventReader<LifeLost>,
+    enemies: Query<(), With<WaveEnemy>>,
+    players: Query<(), (With<Player>, Without<Invulnerable>)>,
+    mut sounds: EventWriter<PlaySfx>,
+) {
+    for hit in damage.read() {
+        if enemies.contains(hit.target) {
+            sounds.send(PlaySfx(Sfx::EnemyHit));
+        } else if players.contains(hit.target) {
+            sounds.send(PlaySfx(Sfx::PlayerHit));
+        }
+    }
+    for death in deaths.read() {
+        if enemies.contains(death.entity) {
+            sounds.send(PlaySfx(Sfx::EnemyDestroyed));
+        }
+    }
+    for _ in lives.read() {
+        sounds.send(PlaySfx(Sfx::LifeLost));
+    }
+}
+
+fn apply_master_volume(settings: Res<UserSettings>, mut global: ResMut<GlobalVolume>) {
+    *global = GlobalVolume::new(settings.volume.master);
+}
+
+fn play_sounds(
+    mut requests: EventReader<PlaySfx>,
+    playing: Query<&PlayingSfx>,
+    handles: Option<Res<SfxHandles>>,
+    settings: Res<UserSettings>,
+    mut commands: Commands,
+) {
+    let Some(handles) = handles else {
+        requests.clear();
+        return;
+    };
+    let mut instances = HashMap::<Sfx, usize>::new();
+    for PlayingSfx(sfx) in playing.iter() {
+        *instances.entry(*sfx).or_default() += 1;
+    }
+
+    for PlaySfx(sfx) in requests.read() {
+        let count = instances.entry(*sfx).or_default();
+        if *count >= MAX_INSTANCES || settings.volume.sfx <= 0. {
+            continue;
+        }
+        *count += 1;
+
+        let (volume, speed) = sfx.playback();
+        commands.spawn((
+            AudioBundle {
+                source: handles.0[sfx].clone(),
+                settings: PlaybackSettings::DESPAWN
+                    .with_volume(Volume::new(volume * settings.volume.sfx))
+                    .with_speed(speed),
+            },
+            PlayingSfx(*sfx),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_sounds_are_limited() {
+        let mut app = App::new();
+        app.add_event::<PlaySfx>()
+            .init_resource::<UserSettings>()
+            .insert_resource(SfxHandles(
+                Sfx::ALL
+                    .into_iter()
+                    .map(|sfx| (sfx, Handle::default()))
+                    .collect(),
+            ))
+            .add_systems(Update, play_sounds);
+
+        for _ in 0..3 {
+            for _ in 0..3 {
+                app.world.send_event(PlaySfx(Sfx::PlayerShot));
+            }
+            app.world.send_event(PlaySfx(Sfx::EnemyShot));
+            app.update();
+        }
+
+        let mut counts = HashMap::<Sfx, usize>::new();
+        for PlayingSfx(sfx) in app.world.query::<&PlayingSfx>().iter(&app.world) {
+            *counts.entry(*sfx).or_default() += 1;
+        }
+        assert_eq!(counts[&Sfx::PlayerShot], MAX_INSTANCES);
+        assert_eq!(counts[&Sfx::EnemyShot], 3);
+    }
+}
@@ -0,0 +1,293 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    collision::{Collider, CollisionLayer},
+    config::ConfigError,
+    effects::HitFlash,
+    health::Health,
+    patterns::{aim, BulletPattern, Emitter},
+    player::Player,
+    projectile::{spawn_projectile, ShotFired},
+    score::ScoreValue,
+    waves::{WaveDirector, WaveEnemy, WaveStarted},
+    PlayArea, RoundEntity, TickSet, SHIP_SPRITE_SIZE,
+};
+
+// how far behind the right edge the boss appears
+const SPAWN_MARGIN: f32 = 300.;
+// units per second the boss flies in with
+const ENTRY_SPEED: f32 = 250.;
+// distance kept from the top and bottom edge while moving up and down
+const EDGE_MARGIN: f32 = 150.;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BossPhase {
+    // the phase starts once the boss is down to this fraction of its hp
+    pub from_hp: f32,
+    // units per second the boss moves up and down with
+    pub speed: f32,
+    pub patterns: Vec<BulletPattern>,
+}
+
+// Boss fought after the last wave, beating it wins the game
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BossDefinition {
+    pub name: String,
+    // image in the assets folder
+    pub sprite: String,
+    // hex color the sprite is multiplied with
+    pub tint: String,
+    pub scale: f32,
+    pub hp: f32,
+    pub points: u32,
+    // where it stops, a fraction of the half width measured from the center
+    pub stop_x: f32,
+    // ordered from full hp down
+    pub phases: Vec<BossPhase>,
+}
+
+impl BossDefinition {
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: &str| {
+            Err(ConfigError::Invalid {
+                field,
+                reason: reason.into(),
+            })
+        };
+
+        if Color::hex(&self.tint).is_err() {
+            return invalid("tint", "must be a hex color");
+        }
+        for (field, value) in [("scale", self.scale), ("hp", self.hp)] {
+            if !(value.is_finite() && value > 0.) {
+                return invalid(field, "must be a positive number");
+            }
+        }
+        if !(0. ..=1.).contains(&self.stop_x) {
+            return invalid("stop_x", "must be between 0 and 1");
+        }
+        if self.phases.first().map(|phase| phase.from_hp) != Some(1.) {
+            return invalid("phases", "the first phase must start from full hp (1.)");
+        }
+        for pair in self.phases.windows(2) {
+            if pair[1].from_hp >= pair[0].from_hp || pair[1].from_hp <= 0. {
+                return invalid("from_hp", "phases must go down from 1 to above 0");
+            }
+        }
+        for phase in &self.phases {
+            if !(phase.speed.is_finite() && phase.speed >= 0.) {
+                return invalid("speed", "must not be negative");
+            }
+            for pattern in &phase.patterns {
+                pattern.validate()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // index of the phase for a boss at `health` (0 to 1)
+    pub fn phase_at(&self, health: f32) -> usize {
+        self.phases
+            .iter()
+            .rposition(|phase| health <= phase.from_hp)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Boss {
+    pub name: String,
+    definition: BossDefinition,
+    phase: usize,
+    emitters: Vec<Emitter>,
+    // false while it's still flying in
+    arrived: bool,
+    // 1 moving up, -1 moving down
+    heading: f32,
+}
+
+impl Boss {
+    fn new(definition: BossDefinition) -> Self {
+        let mut boss = Self {
+            name: definition.name.clone(),
+            definition,
+            phase: 0,
+            emitters: Vec::new(),
+            arrived: false,
+            heading: 1.,
+        };
+        boss.enter_phase(0);
+        boss
+    }
+
+    pub fn phase(&self) -> usize {
+        self.phase
+    }
+
+    fn enter_phase(&mut self, phase: usize) {
+        self.phase = phase;
+        self.emitters = self.definition.phases[phase]
+            .patterns
+            .iter()
+            .copied()
+            .map(Emitter::new)
+            .collect();
+    }
+}
+
+// Spawns the boss of the wave list once its waves are cleared
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                spawn_boss.in_set(TickSet::Spawn),
+                (switch_boss_phase, shoot_boss_patterns)
+                    .chain()
+                    .in_set(TickSet::Shoot),
+                boss_movement.in_set(TickSet::Move),
+            ),
+        );
+    }
+}
+
+fn spawn_boss(
+    mut started: EventReader<WaveStarted>,
+    director: Res<WaveDirector>,
+    play_area: Res<PlayArea>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    for wave in started.read() {
+        if !wave.boss {
+            continue;
+        }
+        let Some(definition) = director.boss() else {
+            continue;
+        };
+
+        let position = Vec3::new(
+            play_area.bounds.max.x + SPAWN_MARGIN,
+            play_area.bounds.center().y,
+            0.,
+        );
+        let color = Color::hex(&definition.tint).unwrap_or(Color::WHITE);
+        commands.spawn((
+            SpriteBundle {
+                texture: asset_server.load(definition.sprite.clone()),
+                sprite: Sprite { color, ..default() },
+                transform: Transform::from_translation(position)
+                    .with_scale(Vec3::splat(definition.scale)),
+                ..default()
+            },
+            Collider::circle_from_sprite(SHIP_SPRITE_SIZE, definition.scale, CollisionLayer::Enemy),
+            Health::new(definition.hp),
+            ScoreValue(definition.points),
+            Boss::new(definition.clone()),
+            HitFlash::new(color),
+            WaveEnemy,
+            RoundEntity,
+        ));
+    }
+}
+
+fn switch_boss_phase(mut bosses: Query<(&mut Boss, &Health)>) {
+    for (mut boss, health) in bosses.iter_mut() {
+        let phase = boss.definition.phase_at(health.current / health.max);
+        if phase != boss.phase {
+            debug!("{} enters phase {}", boss.name, phase + 1);
+            boss.enter_phase(phase);
+        }
+    }
+}
+
+fn shoot_boss_patterns(
+    mut bosses: Query<(&Transform, &mut Boss)>,
+    players: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut shots: EventWriter<ShotFired>,
+) {
+    let target = players.get_single().ok().map(|player| player.translation);
+    for (transform, mut boss) in bosses.iter_mut() {
+        if !boss.arrived {
+            continue;
+        }
+        let aim = aim(transform.translation, target);
+
+        for emitter in boss.emitters.iter_mut() {
+            for velocity in emitter.fire(time.delta(), aim) {
+                spawn_projectile(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    transform.translation,
+                    velocity,
+                    false,
+                );
+                shots.send(ShotFired { by_player: false });
+            }
+        }
+    }
+}
+
+// flies in to its spot, then moves up and down between the edges
+fn boss_movement(
+    mut bosses: Query<(&mut Transform, &mut Boss)>,
+    play_area: Res<PlayArea>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    let bounds = play_area.bounds;
+    for (mut transform, mut boss) in bosses.iter_mut() {
+        let stop_x = bounds.center().x + boss.definition.stop_x * bounds.half_size().x;
+        if !boss.arrived {
+            transform.translation.x = (transform.translation.x - ENTRY_SPEED * delta).max(stop_x);
+            boss.arrived = transform.translation.x <= stop_x;
+            continue;
+        }
+
+        let speed = boss.definition.phases[boss.phase].speed;
+        let top = bounds.max.y - EDGE_MARGIN;
+        let bottom = bounds.min.y + EDGE_MARGIN;
+        transform.translation.y += boss.heading * speed * delta;
+        if transform.translation.y >= top {
+            transform.translation.y = top;
+            boss.heading = -1.;
+        } else if transform.translation.y <= bottom {
+            transform.translation.y = bottom;
+            boss.heading = 1.;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::RonAsset, waves::WaveList};
+
+    fn definition() -> BossDefinition {
+        WaveList::from_bytes(include_bytes!("../assets/waves.ron"))
+            .unwrap()
+            .boss
+            .unwrap()
+    }
+
+    #[test]
+    fn phases_switch_at_their_thresholds() {
+        let definition = definition();
+        let last = definition.phases.len() - 1;
+
+        assert_eq!(definition.phase_at(1.), 0);
+        assert_eq!(definition.phase_at(definition.phases[1].from_hp), 1);
+        assert_eq!(definition.phase_at(0.01), last);
+    }
+}
@@ -0,0 +1,285 @@
+use bevy::{prelude::*, utils::HashMap};
+
+// size of a spatial hash cell, roughly the size of the biggest collider
+const CELL_SIZE: f32 = 128.;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CollisionLayer {
+    Player,
+    Enemy,
+    PlayerShot,
+    EnemyShot,
+    PowerUp,
+}
+
+impl CollisionLayer {
+    // which layers can touch each other, everything else is skipped before the narrow phase
+    pub fn interacts_with(self, other: CollisionLayer) -> bool {
+        use CollisionLayer::*;
+
+        matches!(
+            (self.min(other), self.max(other)),
+            (Player, Enemy)
+                | (Player, EnemyShot)
+                | (Player, PowerUp)
+                | (Enemy, PlayerShot)
+                | (PlayerShot, EnemyShot)
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColliderShape {
+    Circle { radius: f32 },
+    Aabb { half_extents: Vec2 },
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub layer: CollisionLayer,
+}
+
+impl Collider {
+    pub fn circle(radius: f32, layer: CollisionLayer) -> Self {
+        Self {
+            shape: ColliderShape::Circle { radius },
+            layer,
+        }
+    }
+
+    pub fn aabb(half_extents: Vec2, layer: CollisionLayer) -> Self {
+        Self {
+            shape: ColliderShape::Aabb { half_extents },
+            layer,
+        }
+    }
+
+    // circle inscribed into a sprite of `size` pixels drawn with uniform `scale`
+    pub fn circle_from_sprite(size: Vec2, scale: f32, layer: CollisionLayer) -> Self {
+        Self::circle(size.min_element() * scale / 2., layer)
+    }
+
+    fn half_extents(&self) -> Vec2 {
+        match self.shape {
+            ColliderShape::Circle { radius } => Vec2::splat(radius),
+            ColliderShape::Aabb { half_extents } => half_extents,
+        }
+    }
+
+    pub fn overlaps(&self, pos: Vec2, other: &Collider, other_pos: Vec2) -> bool {
+        match (self.shape, other.shape) {
+            (ColliderShape::Circle { radius: r1 }, ColliderShape::Circle { radius: r2 }) => {
+                pos.distance_squared(other_pos) < (r1 + r2) * (r1 + r2)
+            }
+            (
+                ColliderShape::Aabb { half_extents: h1 },
+                ColliderShape::Aabb { half_extents: h2 },
+            ) => {
+                let delta = (pos - other_pos).abs();
+                delta.x < h1.x + h2.x && delta.y < h1.y + h2.y
+            }
+            (ColliderShape::Circle { radius }, ColliderShape::Aabb { half_extents }) => {
+                circle_aabb_overlap(pos, radius, other_pos, half_extents)
+            }
+            (ColliderShape::Aabb { half_extents }, ColliderShape::Circle { radius }) => {
+                circle_aabb_overlap(other_pos, radius, pos, half_extents)
+            }
+        }
+    }
+}
+
+fn circle_aabb_overlap(center: Vec2, radius: f32, box_center: Vec2, half_extents: Vec2) -> bool {
+    let closest = center.clamp(box_center - half_extents, box_center + half_extents);
+    center.distance_squared(closest) < radius * radius
+}
+
+// Sent once per frame for every pair of overlapping colliders whose layers interact
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CollisionEvent {
+    pub a: (Entity, CollisionLayer),
+    pub b: (Entity, CollisionLayer),
+}
+
+impl CollisionEvent {
+    // entities of the pair ordered as (`first`, `second`), if the pair is between those layers
+    pub fn between(
+        &self,
+        first: CollisionLayer,
+        second: CollisionLayer,
+    ) -> Option<(Entity, Entity)> {
+        if self.a.1 == first && self.b.1 == second {
+            Some((self.a.0, self.b.0))
+        } else if self.a.1 == second && self.b.1 == first {
+            Some((self.b.0, self.a.0))
+        } else {
+            None
+        }
+    }
+}
+
+// Uniform grid bucketing collider bounds, so only colliders sharing a cell are tested
+pub struct SpatialHashGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<usize>>,
+}
+
+impl Default for SpatialHashGrid {
+    fn default() -> Self {
+        Self::new(CELL_SIZE)
+    }
+}
+
+impl SpatialHashGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        // keep the allocations around for the next frame
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, min: Vec2, max: Vec2) {
+        let min = (min / self.cell_size).floor().as_ivec2();
+        let max = (max / self.cell_size).floor().as_ivec2();
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    // every pair of indices sharing at least one cell, sorted and without duplicates
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+
+        for cell in self.cells.values() {
+            for (i, &a) in cell.iter().enumerate() {
+                for &b in &cell[i + 1..] {
+                    pairs.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CollisionDetection;
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CollisionEvent>()
+            .add_systems(FixedUpdate, detect_collisions.in_set(CollisionDetection));
+    }
+}
+
+fn detect_collisions(
+    colliders: Query<(Entity, &Transform, &Collider)>,
+    mut grid: Local<SpatialHashGrid>,
+    mut events: EventWriter<CollisionEvent>,
+) {
+    let colliders: Vec<_> = colliders
+        .iter()
+        .map(|(entity, transform, collider)| (entity, transform.translation.truncate(), *collider))
+        .collect();
+
+    grid.clear();
+    for (index, (_, pos, collider)) in colliders.iter().enumerate() {
+        let half_extents = collider.half_extents();
+        grid.insert(index, *pos - half_extents, *pos + half_extents);
+    }
+
+    for (i, j) in grid.candidate_pairs() {
+        let (entity_a, pos_a, collider_a) = colliders[i];
+        let (entity_b, pos_b, collider_b) = colliders[j];
+
+        if collider_a.layer.interacts_with(collider_b.layer)
+            && collider_a.overlaps(pos_a, &collider_b, pos_b)
+        {
+            events.send(CollisionEvent {
+                a: (entity_a, collider_a.layer),
+                b: (entity_b, collider_b.layer),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_overlap() {
+        let circle = Collider::circle(10., CollisionLayer::Player);
+        let aabb = Collider::aabb(Vec2::new(12., 4.), CollisionLayer::EnemyShot);
+
+        assert!(circle.overlaps(Vec2::ZERO, &circle, Vec2::new(19., 0.)));
+        assert!(!circle.overlaps(Vec2::ZERO, &circle, Vec2::new(21., 0.)));
+        assert!(circle.overlaps(Vec2::ZERO, &aabb, Vec2::new(21., 0.)));
+        assert!(!aabb.overlaps(Vec2::new(0., 15.), &circle, Vec2::ZERO));
+        assert!(aabb.overlaps(Vec2::ZERO, &aabb, Vec2::new(23., 7.)));
+        assert!(!aabb.overlaps(Vec2::ZERO, &aabb, Vec2::new(25., 0.)));
+    }
+
+    #[test]
+    fn grid_pairs_only_share_cells() {
+        let mut grid = SpatialHashGrid::new(100.);
+        grid.insert(0, Vec2::new(10., 10.), Vec2::new(20., 20.));
+        grid.insert(1, Vec2::new(90., 10.), Vec2::new(110., 20.));
+        grid.insert(2, Vec2::new(150., 10.), Vec2::new(160., 20.));
+        grid.insert(3, Vec2::new(500., 500.), Vec2::new(510., 510.));
+
+        assert_eq!(grid.candidate_pairs(), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn detects_only_interacting_layers() {
+        let mut app = App::new();
+        app.add_plugins(CollisionPlugin);
+
+        let player = app
+            .world
+            .spawn((
+                Transform::default(),
+                Collider::circle(50., CollisionLayer::Player),
+            ))
+            .id();
+        let rocket = app
+            .world
+            .spawn((
+                Transform::from_xyz(60., 0., 0.),
+                Collider::circle(50., CollisionLayer::Enemy),
+            ))
+            .id();
+        // player shots never hit the player
+        app.world.spawn((
+            Transform::from_xyz(10., 0., 0.),
+            Collider::aabb(Vec2::new(12., 4.), CollisionLayer::PlayerShot),
+        ));
+
+        app.world.run_schedule(FixedUpdate);
+
+        let events: Vec<_> = app
+            .world
+            .resource_mut::<Events<CollisionEvent>>()
+            .drain()
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|event| event
+            .between(CollisionLayer::Enemy, CollisionLayer::Player)
+            == Some((rocket, player))));
+    }
+}
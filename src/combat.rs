@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    boss::Boss,
+    collision::{CollisionEvent, CollisionLayer},
+    config::GameConfig,
+    health::{DamageEvent, DeathEvent, Health, Shield},
+    player::Player,
+    projectile::{Damage, Piercing},
+    GameState, TickSet,
+};
+
+// Turns collisions into damage and despawns whatever dies
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                (
+                    rocket_projectile_player_collision_system,
+                    player_projectile_rocket_collision_system,
+                    player_rocket_projectile_collision,
+                    rocket_player_collision_system,
+                )
+                    .chain()
+                    .in_set(TickSet::Resolve),
+                death_system.in_set(TickSet::Cleanup),
+            ),
+        );
+    }
+}
+
+// piercing shots keep going and hit every enemy once, the others stop at the first
+fn player_projectile_rocket_collision_system(
+    mut collisions: EventReader<CollisionEvent>,
+    mut piercing: Query<&mut Piercing>,
+    damages: Query<&Damage>,
+    mut damage: EventWriter<DamageEvent>,
+    mut commands: Commands,
+) {
+    let mut spent = HashSet::new();
+
+    for collision in collisions.read() {
+        let Some((entity_proj, entity_obst)) =
+            collision.between(CollisionLayer::PlayerShot, CollisionLayer::Enemy)
+        else {
+            continue;
+        };
+        if let Ok(mut piercing) = piercing.get_mut(entity_proj) {
+            if !piercing.first_hit(entity_obst) {
+                continue;
+            }
+        } else {
+            if !spent.insert(entity_proj) {
+                continue;
+            }
+            commands.entity(entity_proj).despawn();
+        }
+
+        damage.send(DamageEvent {
+            target: entity_obst,
+            amount: damages.get(entity_proj).map_or(1., |damage| damage.0),
+            source: entity_proj,
+        });
+    }
+}
+
+fn rocket_projectile_player_collision_system(
+    mut collisions: EventReader<CollisionEvent>,
+    mut damage: EventWriter<DamageEvent>,
+    mut commands: Commands,
+) {
+    let mut spent = HashSet::new();
+
+    for collision in collisions.read() {
+        let Some((entity_proj, entity_pl)) =
+            collision.between(CollisionLayer::EnemyShot, CollisionLayer::Player)
+        else {
+            continue;
+        };
+        if !spent.insert(entity_proj) {
+            continue;
+        }
+
+        commands.entity(entity_proj).despawn();
+
+        damage.send(DamageEvent {
+            target: entity_pl,
+            amount: 1.,
+            source: entity_proj,
+        });
+    }
+}
+
+// the rammed enemy takes lethal damage, so it dies (and scores) like any other kill
+fn rocket_player_collision_system(
+    mut collisions: EventReader<CollisionEvent>,
+    bosses: Query<(), With<Boss>>,
+    enemies: Query<(&Health, Option<&Shield>)>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let mut spent = HashSet::new();
+
+    for collision in collisions.read() {
+        let Some((entity_roc, entity_pl)) =
+            collision.between(CollisionLayer::Enemy, CollisionLayer::Player)
+        else {
+            continue;
+        };
+        // bosses stay out of the player's half and can't be rammed
+        if bosses.contains(entity_roc) || !spent.insert(entity_roc) {
+            continue;
+        }
+
+        damage.send(DamageEvent {
+            target: entity_pl,
+            amount: 1.,
+            source: entity_roc,
+        });
+        if let Ok((health, shield)) = enemies.get(entity_roc) {
+            damage.send(DamageEvent {
+                target: entity_roc,
+                amount: health.current + shield.map_or(0., |shield| shield.current),
+                source: entity_pl,
+            });
+        }
+    }
+}
+
+fn player_rocket_projectile_collision(
+    mut collisions: EventReader<CollisionEvent>,
+    piercing: Query<(), With<Piercing>>,
+    config: Res<GameConfig>,
+    mut commands: Commands,
+) {
+    if !config.player_rocket_projectiles_collision {
+        collisions.clear();
+        return;
+    }
+
+    let mut spent = HashSet::new();
+
+    for collision in collisions.read() {
+        if let Some((entity1, entity2)) =
+            collision.between(CollisionLayer::PlayerShot, CollisionLayer::EnemyShot)
+        {
+            // piercing shots clear the enemy shots on their way
+            let pierces = piercing.contains(entity1);
+            if (pierces || spent.insert(entity1)) && spent.insert(entity2) {
+                if !pierces {
+                    commands.entity(entity1).despawn();
+                }
+                commands.entity(entity2).despawn();
+            }
+        }
+    }
+}
+
+// despawns whatever ran out of health, beating the boss ends the round, the player is
+// handled by `player::lose_life`
+fn death_system(
+    mut deaths: EventReader<DeathEvent>,
+    players: Query<(), With<Player>>,
+    bosses: Query<(), With<Boss>>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for death in deaths.read() {
+        if players.contains(death.entity) {
+            continue;
+        }
+        let Some(entity) = commands.get_entity(death.entity) else {
+            continue;
+        };
+        entity.despawn_recursive();
+
+        if bosses.contains(death.entity) {
+            next_state.set(GameState::Victory);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::{marker::PhantomData, time::Duration};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use thiserror::Error;
+
+// path of the settings file, relative to the assets folder
+const CONFIG_PATH: &str = "config.ron";
+
+// Settings of the game
+//
+// Loaded from `assets/config.ron`, every field missing from the file falls back to the
+// default below. The file is watched, so edits are applied while the game is running.
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    // radius of a player ship
+    pub player_radius: f32,
+    // top speed of the player (units per second)
+    pub player_speed: f32,
+    // how fast the player speeds up (units per second squared)
+    pub player_acceleration: f32,
+    // how fast the player slows down without input (units per second squared)
+    pub player_friction: f32,
+    // player projectile speed (units per second)
+    pub player_projectile_speed: f32,
+    // player max hp
+    pub player_max_hp: f32,
+    // lives the player starts every round with
+    pub player_lives: u32,
+    // how long the player can't be hurt after respawning, in milliseconds
+    pub respawn_invulnerability: u64,
+    // player projectile cooldown in milliseconds
+    pub player_projectile_cd: u64,
+    // bombs the player starts every round with
+    pub player_bombs: u32,
+    // damage a bomb deals to every enemy on screen
+    pub bomb_damage: f32,
+    // chance of a destroyed enemy dropping a power-up, from 0 to 1
+    pub powerup_drop_chance: f32,
+    // speed power-ups float to the left with (units per second)
+    pub powerup_speed: f32,
+    // how long rapid fire and the shield last, in milliseconds
+    pub powerup_duration: u64,
+    // hp restored by a heal power-up
+    pub powerup_heal: f32,
+    // damage the shield power-up absorbs
+    pub powerup_shield: f32,
+    // can player and rocket projectiles collide
+    pub player_rocket_projectiles_collision: bool,
+    // seed of every round, a random one is picked when it's not set
+    pub seed: Option<u64>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            player_radius: 70.,
+            player_speed: 600.,
+            player_acceleration: 6000.,
+            player_friction: 4000.,
+            player_projectile_speed: 600.,
+            player_max_hp: 8.,
+            player_lives: 3,
+            respawn_invulnerability: 2000,
+            player_projectile_cd: 350,
+            player_bombs: 3,
+            bomb_damage: 3.,
+            powerup_drop_chance: 0.12,
+            powerup_speed: 150.,
+            powerup_duration: 8000,
+            powerup_heal: 2.,
+            powerup_shield: 3.,
+            player_rocket_projectiles_collision: true,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("could not read file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("invalid value for `{field}`: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+// Data file in `assets/` written in RON and checked after parsing
+pub trait RonAsset: Asset + DeserializeOwned {
+    // full extension of the file, e.g. `config.ron`
+    const EXTENSION: &'static str;
+
+    fn validate(&self) -> Result<(), ConfigError>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ConfigError> {
+        let asset: Self = ron::de::from_bytes(bytes)?;
+        asset.validate()?;
+        Ok(asset)
+    }
+}
+
+pub struct RonAssetLoader<A>(PhantomData<A>);
+
+impl<A> Default for RonAssetLoader<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = ConfigError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<A, ConfigError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            A::from_bytes(&bytes)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        std::slice::from_ref(&A::EXTENSION)
+    }
+}
+
+impl RonAsset for GameConfig {
+    const EXTENSION: &'static str = "config.ron";
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let positive = [
+            ("player_radius", self.player_radius),
+            ("player_speed", self.player_speed),
+            ("player_acceleration", self.player_acceleration),
+            ("player_friction", self.player_friction),
+            ("player_projectile_speed", self.player_projectile_speed),
+            ("player_max_hp", self.player_max_hp),
+            ("bomb_damage", self.bomb_damage),
+            ("powerup_speed", self.powerup_speed),
+            ("powerup_heal", self.powerup_heal),
+            ("powerup_shield", self.powerup_shield),
+        ];
+        for (field, value) in positive {
+            if !(value.is_finite() && value > 0.) {
+                return Err(ConfigError::Invalid {
+                    field,
+                    reason: format!("must be a positive number, got {}", value),
+                });
+            }
+        }
+
+        if self.player_projectile_cd == 0 {
+            return Err(ConfigError::Invalid {
+                field: "player_projectile_cd",
+                reason: "cooldown must be at least 1 millisecond".into(),
+            });
+        }
+
+        if self.player_lives == 0 {
+            return Err(ConfigError::Invalid {
+                field: "player_lives",
+                reason: "the player needs at least 1 life".into(),
+            });
+        }
+
+        if !(0. ..=1.).contains(&self.powerup_drop_chance) {
+            return Err(ConfigError::Invalid {
+                field: "powerup_drop_chance",
+                reason: format!("must be between 0 and 1, got {}", self.powerup_drop_chance),
+            });
+        }
+
+        if self.powerup_duration == 0 {
+            return Err(ConfigError::Invalid {
+                field: "powerup_duration",
+                reason: "must be at least 1 millisecond".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl GameConfig {
+    pub fn player_projectile_cd(&self) -> Duration {
+        Duration::from_millis(self.player_projectile_cd)
+    }
+
+    pub fn respawn_invulnerability(&self) -> Duration {
+        Duration::from_millis(self.respawn_invulnerability)
+    }
+
+    pub fn powerup_duration(&self) -> Duration {
+        Duration::from_millis(self.powerup_duration)
+    }
+}
+
+#[derive(Resource)]
+pub struct GameConfigHandle(Handle<GameConfig>);
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<GameConfig>()
+            .init_asset_loader::<RonAssetLoader<GameConfig>>()
+            .init_resource::<GameConfig>()
+            .add_systems(Startup, load_config)
+            .add_systems(Update, apply_config);
+    }
+}
+
+fn load_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameConfigHandle(asset_server.load(CONFIG_PATH)));
+}
+
+// copies the loaded (or hot reloaded) file into the `GameConfig` resource,
+// a file that fails to load or validate keeps the previous settings
+fn apply_config(
+    mut events: EventReader<AssetEvent<GameConfig>>,
+    handle: Res<GameConfigHandle>,
+    configs: Res<Assets<GameConfig>>,
+    mut config: ResMut<GameConfig>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } if *id == handle.0.id() => {
+                if let Some(loaded) = configs.get(*id) {
+                    info!("applied game config from assets/{}", CONFIG_PATH);
+                    *config = loaded.clone();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// true once the settings file is applied (or failed to load), used to start replays
+// with the same settings they were recorded with
+pub fn config_settled(
+    handle: Option<Res<GameConfigHandle>>,
+    asset_server: Res<AssetServer>,
+    configs: Res<Assets<GameConfig>>,
+    config: Res<GameConfig>,
+) -> bool {
+    let Some(handle) = handle else {
+        return false;
+    };
+    match asset_server.load_state(&handle.0) {
+        LoadState::Loaded => configs.get(&handle.0) == Some(&*config),
+        LoadState::Failed => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_config_matches_defaults() {
+        let config = GameConfig::from_bytes(include_bytes!("../assets/config.ron")).unwrap();
+
+        assert_eq!(config, GameConfig::default());
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let config = GameConfig::from_bytes(b"(player_speed: 300.)").unwrap();
+
+        assert_eq!(config.player_speed, 300.);
+        assert_eq!(config.player_max_hp, GameConfig::default().player_max_hp);
+    }
+
+    #[test]
+    fn invalid_values_name_the_field() {
+        let err = GameConfig::from_bytes(b"(player_max_hp: -1.)").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "invalid value for `player_max_hp`: must be a positive number, got -1"
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        assert!(matches!(
+            GameConfig::from_bytes(b"(player_sped: 300.)"),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+}
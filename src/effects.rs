@@ -0,0 +1,269 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    boss::Boss,
+    health::{DamageEvent, DeathEvent, Health, Invulnerable},
+    RoundEntity, TickSet,
+};
+
+// how long a sprite stays tinted after taking damage
+const HIT_FLASH: Duration = Duration::from_millis(120);
+// tint of a sprite that was just hit
+const FLASH_COLOR: Color = Color::rgb(1., 0.25, 0.25);
+// share of their speed particles lose per second
+const PARTICLE_DRAG: f32 = 2.;
+
+// How the particles of a burst look and move
+struct Burst {
+    count: usize,
+    // units per second, picked at random between the two
+    speed: (f32, f32),
+    // seconds
+    lifetime: f32,
+    // size at the start and at the end of the lifetime
+    size: (f32, f32),
+    color: (Color, Color),
+}
+
+const HIT: Burst = Burst {
+    count: 6,
+    speed: (150., 350.),
+    lifetime: 0.25,
+    size: (8., 2.),
+    color: (Color::rgb(1., 1., 0.8), Color::rgba(1., 0.6, 0.2, 0.)),
+};
+
+const EXPLOSION: Burst = Burst {
+    count: 24,
+    speed: (80., 320.),
+    lifetime: 0.6,
+    size: (18., 4.),
+    color: (Color::rgb(1., 0.85, 0.3), Color::rgba(0.8, 0.1, 0., 0.)),
+};
+
+const BOSS_EXPLOSION: Burst = Burst {
+    count: 80,
+    speed: (100., 700.),
+    lifetime: 1.4,
+    size: (40., 8.),
+    color: (Color::rgb(1., 0.9, 0.6), Color::rgba(0.6, 0.1, 0.8, 0.)),
+};
+
+// Short-lived square flying off a hit or an explosion
+#[derive(Component, Debug)]
+pub struct Particle {
+    velocity: Vec2,
+    age: Timer,
+    size: (f32, f32),
+    color: (Color, Color),
+}
+
+// Tints a sprite for a moment after it took damage, then puts its color back
+//
+// Given to a ship when it's spawned and kept for its whole life, a hit only restarts
+// the timer so no component comes and goes in the middle of a round.
+#[derive(Component, Debug)]
+pub struct HitFlash {
+    timer: Timer,
+    // color of the sprite when it isn't flashing
+    color: Color,
+}
+
+impl HitFlash {
+    pub fn new(color: Color) -> Self {
+        let mut timer = Timer::new(HIT_FLASH, TimerMode::Once);
+        timer.tick(HIT_FLASH);
+        Self { timer, color }
+    }
+}
+
+// Particle bursts for hits and deaths, and the hit flash of sprites
+//
+// Purely visual, the randomness doesn't come from `rng::GameRng` and the ships keep
+// the same components, so a round plays out the same with or without it.
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (spawn_hit_effects, update_hit_flashes)
+                .chain()
+                .in_set(TickSet::Effects),
+        )
+        .add_systems(Update, update_particles);
+    }
+}
+
+fn spawn_burst(commands: &mut Commands, rng: &mut impl Rng, position: Vec3, burst: &Burst) {
+    for _ in 0..burst.count {
+        let direction = Vec2::from_angle(rng.gen_range(0. ..std::f32::consts::TAU));
+        let speed = rng.gen_range(burst.speed.0..burst.speed.1);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: burst.color.0,
+                    custom_size: Some(Vec2::splat(burst.size.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.truncate().extend(1.)),
+                ..default()
+            },
+            Particle {
+                velocity: direction * speed,
+                age: Timer::from_seconds(burst.lifetime, TimerMode::Once),
+                size: burst.size,
+                color: burst.color,
+            },
+            RoundEntity,
+        ));
+    }
+}
+
+// runs while whatever died this tick is still around
+fn spawn_hit_effects(
+    mut damage: EventReader<DamageEvent>,
+    mut deaths: EventReader<DeathEvent>,
+    mut targets: Query<(&Transform, Option<&mut HitFlash>)>,
+    ships: Query<(&Health, Has<Boss>)>,
+    invulnerable: Query<(), With<Invulnerable>>,
+    mut commands: Commands,
+) {
+    let mut rng = rand::thread_rng();
+
+    for hit in damage.read() {
+        if invulnerable.contains(hit.target) {
+            continue;
+        }
+        let Ok((transform, flash)) = targets.get_mut(hit.target) else {
+            continue;
+        };
+        spawn_burst(&mut commands, &mut rng, transform.translation, &HIT);
+
+        if ships
+            .get(hit.target)
+            .is_ok_and(|(health, _)| health.is_dead())
+        {
+            continue;
+        }
+        if let Some(mut flash) = flash {
+            flash.timer.reset();
+        }
+    }
+
+    for death in deaths.read() {
+        // the player respawns right away, the explosion marks where they were
+        let (Ok((transform, ..)), Ok((_, boss))) =
+            (targets.get(death.entity), ships.get(death.entity))
+        else {
+            continue;
+        };
+        let burst = if boss { &BOSS_EXPLOSION } else { &EXPLOSION };
+        spawn_burst(&mut commands, &mut rng, transform.translation, burst);
+    }
+}
+
+fn update_particles(
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in particles.iter_mut() {
+        if particle.age.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let slowdown = (1. - PARTICLE_DRAG * delta).max(0.);
+        particle.velocity *= slowdown;
+        transform.translation += (particle.velocity * delta).extend(0.);
+
+        let t = particle.age.fraction();
+        let size = particle.size.0 + (particle.size.1 - particle.size.0) * t;
+        sprite.custom_size = Some(Vec2::splat(size));
+        sprite.color = mix(particle.color.0, particle.color.1, t);
+    }
+}
+
+fn update_hit_flashes(mut flashes: Query<(&mut HitFlash, &mut Sprite)>, time: Res<Time>) {
+    for (mut flash, mut sprite) in flashes.iter_mut() {
+        if flash.timer.finished() {
+            continue;
+        }
+        sprite.color = if flash.timer.tick(time.delta()).finished() {
+            flash.color
+        } else {
+            FLASH_COLOR
+        };
+    }
+}
+
+fn mix(from: Color, to: Color, t: f32) -> Color {
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    let color = from.lerp(to, t);
+    Color::rgba(color.x, color.y, color.z, color.w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_app;
+
+    #[test]
+    fn particles_shrink_fade_and_disappear() {
+        let mut app = test_app(Duration::from_millis(250), update_particles);
+        let particle = app
+            .world
+            .spawn((
+                SpriteBundle::default(),
+                Particle {
+                    velocity: Vec2::new(100., 0.),
+                    age: Timer::from_seconds(1., TimerMode::Once),
+                    size: (10., 2.),
+                    color: (Color::WHITE, Color::rgba(1., 1., 1., 0.)),
+                },
+            ))
+            .id();
+
+        app.update();
+        app.update();
+        let sprite = app.world.get::<Sprite>(particle).unwrap();
+        assert_eq!(sprite.custom_size, Some(Vec2::splat(6.)));
+        assert!((sprite.color.a() - 0.5).abs() < 1e-5);
+        let x = app.world.get::<Transform>(particle).unwrap().translation.x;
+        assert!(x > 0. && x < 50.);
+
+        app.update();
+        app.update();
+        assert!(app.world.get_entity(particle).is_none());
+    }
+
+    #[test]
+    fn hit_flash_puts_the_color_back() {
+        let mut app = test_app(Duration::from_millis(100), update_hit_flashes);
+        let tint = Color::rgb(0.5, 1., 0.5);
+        let ship = app
+            .world
+            .spawn((
+                Sprite {
+                    color: tint,
+                    ..default()
+                },
+                HitFlash::new(tint),
+            ))
+            .id();
+        app.world.get_mut::<HitFlash>(ship).unwrap().timer.reset();
+
+        app.update();
+        assert_eq!(app.world.get::<Sprite>(ship).unwrap().color, FLASH_COLOR);
+        app.update();
+        app.update();
+        assert_eq!(app.world.get::<Sprite>(ship).unwrap().color, tint);
+        assert!(app.world.get::<HitFlash>(ship).is_some());
+    }
+}
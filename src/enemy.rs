@@ -0,0 +1,369 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+
+use crate::{
+    archetypes::{EnemyArchetypes, MovementPattern},
+    collision::{Collider, CollisionLayer},
+    effects::HitFlash,
+    health::{health_bar, shield_bubble, Health, Shield},
+    movement::{turn_towards, Velocity},
+    patterns::{aim, Emitter},
+    player::Player,
+    projectile::{spawn_projectile, ShotFired},
+    score::ScoreValue,
+    waves::{EnemyKind, SpawnEnemy, WaveEnemy},
+    PlayArea, RoundEntity, TickSet, SHIP_SCALE, SHIP_SPRITE_SIZE,
+};
+
+// half width of the enemy hp bar mesh
+const HP_BAR_HALF_WIDTH: f32 = 20.;
+// how far behind the right edge enemies appear
+const SPAWN_MARGIN: f32 = 100.;
+
+// Enemy ship spawned from one of the `EnemyArchetypes`
+#[derive(Component, Debug)]
+pub struct Enemy {
+    pub kind: EnemyKind,
+    pub speed: f32,
+    pub movement: MovementPattern,
+    // fires on its own cooldown, `None` for ships that don't shoot
+    pub weapon: Option<Emitter>,
+    // seconds since it was spawned
+    pub age: f32,
+    // height it was spawned at, sine flyers weave around it
+    pub base_y: f32,
+}
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                spawn_enemy.in_set(TickSet::Spawn),
+                shoot_projectile_enemy.in_set(TickSet::Shoot),
+                enemy_movement.in_set(TickSet::Move),
+            ),
+        );
+    }
+}
+
+fn spawn_enemy(
+    mut spawns: EventReader<SpawnEnemy>,
+    mut commands: Commands,
+    archetypes: Res<EnemyArchetypes>,
+    asset_server: Res<AssetServer>,
+    play_area: Res<PlayArea>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for SpawnEnemy(order) in spawns.read() {
+        let archetype = archetypes.get(order.enemy);
+        let enemy_pos = Vec3::new(
+            play_area.bounds.max.x + SPAWN_MARGIN + order.offset.x,
+            play_area.bounds.center().y + order.offset.y * play_area.bounds.half_size().y,
+            0.,
+        );
+
+        let transform_enemy = Transform {
+            translation: enemy_pos,
+            scale: Vec3::splat(SHIP_SCALE),
+            ..Default::default()
+        };
+        let speed = archetype.speed * order.speed_multiplier;
+
+        let mut enemy = commands.spawn((
+            SpriteBundle {
+                texture: asset_server.load(archetype.sprite.clone()),
+                sprite: Sprite {
+                    color: archetype.color(),
+                    ..default()
+                },
+                transform: transform_enemy,
+                ..default()
+            },
+            Enemy {
+                kind: order.enemy,
+                speed,
+                movement: archetype.movement,
+                weapon: archetype
+                    .weapon
+                    .filter(|_| order.is_shooting)
+                    .map(Emitter::new),
+                age: 0.,
+                base_y: enemy_pos.y,
+            },
+            Velocity(Vec2::new(-speed, 0.)),
+            Collider::circle_from_sprite(SHIP_SPRITE_SIZE, SHIP_SCALE, CollisionLayer::Enemy),
+            // a weak enough enemy would round down to 0 hp, dead before it's hit
+            Health::new((archetype.hp * order.hp_multiplier).round().max(1.)),
+            ScoreValue(archetype.points),
+            HitFlash::new(archetype.color()),
+            WaveEnemy,
+            RoundEntity,
+        ));
+        if archetype.shield > 0. {
+            enemy.insert(Shield::new(
+                archetype.shield * order.hp_multiplier,
+                archetype.shield_regen,
+            ));
+        }
+        enemy.with_children(|parent| {
+            parent.spawn(health_bar(HP_BAR_HALF_WIDTH, &mut meshes, &mut materials));
+            if archetype.shield > 0. {
+                parent.spawn(shield_bubble(
+                    SHIP_SPRITE_SIZE.max_element() * 0.6,
+                    &mut meshes,
+                    &mut materials,
+                ));
+            }
+        });
+    }
+}
+
+fn shoot_projectile_enemy(
+    mut enemies: Query<(&Transform, &mut Enemy)>,
+    players: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut shots: EventWriter<ShotFired>,
+) {
+    let target = players.get_single().ok().map(|player| player.translation);
+    for (transform, mut enemy) in enemies.iter_mut() {
+        let Some(weapon) = enemy.weapon.as_mut() else {
+            continue;
+        };
+
+        for velocity in weapon.fire(time.delta(), aim(transform.translation, target)) {
+            spawn_projectile(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                transform.translation,
+                velocity,
+                false,
+            );
+            shots.send(ShotFired { by_player: false });
+        }
+    }
+}
+
+fn enemy_movement(
+    mut enemies: Query<(&mut Transform, &mut Velocity, &mut Enemy, Entity)>,
+    players: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut commands: Commands,
+    play_area: Res<PlayArea>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    let target = players
+        .get_single()
+        .ok()
+        .map(|player| player.translation.truncate());
+    let despawn_bounds = play_area.despawn_bounds();
+
+    for (mut transform, mut velocity, mut enemy, entity) in enemies.iter_mut() {
+        enemy.age += delta;
+        let position = transform.translation.truncate();
+
+        match enemy.movement {
+            MovementPattern::Straight => {
+                velocity.0 = Vec2::new(-enemy.speed, 0.);
+            }
+            MovementPattern::Sine {
+                amplitude,
+                frequency,
+            } => {
+                let phase = TAU * frequency * enemy.age;
+                let y = enemy.base_y + amplitude * phase.sin();
+                velocity.0 = Vec2::new(-enemy.speed, (y - position.y) / delta.max(f32::EPSILON));
+            }
+            MovementPattern::Homing { turn_rate } => {
+                // once past the player it keeps going, so it can't circle forever
+                if let Some(target) = target.filter(|target| target.x < position.x) {
+                    velocity.0 = turn_towards(velocity.0, target - position, turn_rate * delta);
+                }
+                velocity.0 = velocity.0.normalize_or_zero() * enemy.speed;
+            }
+            MovementPattern::Turret { stop_x } => {
+                let stop_x = play_area.bounds.center().x + stop_x * play_area.bounds.half_size().x;
+                let speed = enemy
+                    .speed
+                    .min((position.x - stop_x).max(0.) / delta.max(f32::EPSILON));
+                velocity.0 = Vec2::new(-speed, 0.);
+            }
+        }
+
+        transform.translation += (velocity.0 * delta).extend(0.);
+        // the sprite points left
+        if velocity.0 != Vec2::ZERO {
+            transform.rotation = Quat::from_rotation_z(velocity.0.y.atan2(velocity.0.x) - PI);
+        }
+
+        // enemies come in from the right, so only the other sides are checked
+        let position = transform.translation.truncate();
+        if position.x < despawn_bounds.min.x
+            || position.y < despawn_bounds.min.y
+            || position.y > despawn_bounds.max.y
+        {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::{patterns::BulletPattern, projectile::Projectile, test_app, x_of};
+
+    fn spawn_enemy_at(app: &mut App, position: Vec2, movement: MovementPattern) -> Entity {
+        let speed = EnemyArchetypes::default().rocket.speed;
+        app.world
+            .spawn((
+                Transform::from_translation(position.extend(0.)),
+                Velocity(Vec2::new(-speed, 0.)),
+                Enemy {
+                    kind: EnemyKind::Rocket,
+                    speed,
+                    movement,
+                    weapon: None,
+                    age: 0.,
+                    base_y: position.y,
+                },
+            ))
+            .id()
+    }
+
+    fn position_of(app: &App, entity: Entity) -> Vec2 {
+        app.world
+            .get::<Transform>(entity)
+            .unwrap()
+            .translation
+            .truncate()
+    }
+
+    #[test]
+    fn enemies_fire_on_their_own_cooldowns() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .add_event::<ShotFired>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                250,
+            )))
+            .add_systems(Update, shoot_projectile_enemy);
+        app.update();
+        for (cooldown, x) in [(0.5, 0.), (0.75, 100.), (0.25, 200.)] {
+            let enemy = spawn_enemy_at(&mut app, Vec2::new(x, 0.), MovementPattern::Straight);
+            // the last one isn't allowed to shoot
+            let weapon = (x < 200.).then(|| {
+                Emitter::new(BulletPattern::Forward {
+                    speed: 300.,
+                    cooldown,
+                })
+            });
+            app.world.get_mut::<Enemy>(enemy).unwrap().weapon = weapon;
+        }
+
+        let mut fired = Vec::new();
+        for _ in 0..4 {
+            app.update();
+            fired.push(app.world.query::<&Projectile>().iter(&app.world).count());
+        }
+
+        assert_eq!(fired, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn rocket_moves_at_rocket_speed() {
+        let mut app = test_app(Duration::from_millis(100), enemy_movement);
+        let rocket = spawn_enemy_at(&mut app, Vec2::ZERO, MovementPattern::Straight);
+
+        app.update();
+
+        let speed = EnemyArchetypes::default().rocket.speed;
+        assert!((x_of(&app, rocket) + speed * 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn movement_does_not_depend_on_frame_rate() {
+        let mut app_60 = test_app(Duration::from_secs_f64(1. / 60.), enemy_movement);
+        let mut app_120 = test_app(Duration::from_secs_f64(1. / 120.), enemy_movement);
+        let rocket_60 = spawn_enemy_at(&mut app_60, Vec2::new(500., 0.), MovementPattern::Straight);
+        let rocket_120 =
+            spawn_enemy_at(&mut app_120, Vec2::new(500., 0.), MovementPattern::Straight);
+
+        for _ in 0..60 {
+            app_60.update();
+        }
+        for _ in 0..120 {
+            app_120.update();
+        }
+
+        let speed = EnemyArchetypes::default().rocket.speed;
+        assert!((x_of(&app_60, rocket_60) - x_of(&app_120, rocket_120)).abs() < 1e-2);
+        assert!((x_of(&app_60, rocket_60) - (500. - speed)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn sine_flyers_weave_around_their_height() {
+        let mut app = test_app(Duration::from_millis(250), enemy_movement);
+        let sine = MovementPattern::Sine {
+            amplitude: 100.,
+            frequency: 1.,
+        };
+        let flyer = spawn_enemy_at(&mut app, Vec2::new(500., 50.), sine);
+
+        // a quarter and then three quarters of a wave
+        app.update();
+        assert!((position_of(&app, flyer).y - 150.).abs() < 1e-3);
+        app.update();
+        app.update();
+        assert!((position_of(&app, flyer).y + 50.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn turrets_stop_at_their_position() {
+        let mut app = test_app(Duration::from_millis(100), enemy_movement);
+        let turret = spawn_enemy_at(
+            &mut app,
+            Vec2::new(600., 0.),
+            MovementPattern::Turret { stop_x: 0.5 },
+        );
+
+        for _ in 0..100 {
+            app.update();
+        }
+
+        let stop_x = PlayArea::default().bounds.half_size().x * 0.5;
+        assert!((x_of(&app, turret) - stop_x).abs() < 1e-3);
+    }
+
+    #[test]
+    fn kamikazes_turn_towards_the_player() {
+        let mut app = test_app(Duration::from_millis(100), enemy_movement);
+        app.world
+            .spawn((Transform::from_xyz(-500., -400., 0.), Player::default()));
+        let kamikaze = spawn_enemy_at(
+            &mut app,
+            Vec2::new(500., 0.),
+            MovementPattern::Homing { turn_rate: 1. },
+        );
+
+        app.update();
+        let velocity = app.world.get::<Velocity>(kamikaze).unwrap().0;
+
+        assert!(velocity.y < 0.);
+        assert!((velocity.length() - EnemyArchetypes::default().rocket.speed).abs() < 1e-2);
+        assert!(velocity.angle_between(Vec2::NEG_X).abs() <= 0.1 + 1e-4);
+    }
+}
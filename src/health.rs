@@ -0,0 +1,329 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+// x scale of a full health bar mesh
+const HEALTH_BAR_SCALE: f32 = 0.15;
+// how long an invulnerable entity stays shown or hidden while blinking
+const BLINK_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Component, Debug)]
+pub struct Health {
+    pub max: f32,
+    pub current: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { max, current: max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.
+    }
+}
+
+// Takes damage before `Health` does and recharges by `regen` per second
+#[derive(Component, Debug)]
+pub struct Shield {
+    pub max: f32,
+    pub current: f32,
+    pub regen: f32,
+}
+
+impl Shield {
+    pub fn new(max: f32, regen: f32) -> Self {
+        Self {
+            max,
+            current: max,
+            regen,
+        }
+    }
+
+    // what's left of `amount` once the shield has taken its part
+    fn absorb(&mut self, amount: f32) -> f32 {
+        let absorbed = amount.min(self.current);
+        self.current -= absorbed;
+        amount - absorbed
+    }
+}
+
+// Ignores all damage until the timer is over, blinking meanwhile
+#[derive(Component, Debug)]
+pub struct Invulnerable(pub Timer);
+
+impl Invulnerable {
+    pub fn new(duration: Duration) -> Self {
+        Self(Timer::new(duration, TimerMode::Once))
+    }
+}
+
+// Child of an entity with a `Shield`, only visible while the shield is up
+#[derive(Component)]
+pub struct ShieldBubble;
+
+// Child of an entity with `Health`, its mesh is scaled to show the remaining health
+#[derive(Component)]
+pub struct HealthBar;
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub source: Entity,
+}
+
+// Sent once when the health of `entity` drops to zero
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DeathEvent {
+    pub entity: Entity,
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HealthUpdate;
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<DeathEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    recharge_shields,
+                    tick_invulnerability,
+                    apply_damage,
+                    sync_health_bars,
+                    sync_shield_bubbles,
+                )
+                    .chain()
+                    .in_set(HealthUpdate),
+            );
+    }
+}
+
+fn apply_damage(
+    mut damage: EventReader<DamageEvent>,
+    mut healths: Query<(&mut Health, Option<&mut Shield>, Has<Invulnerable>)>,
+    mut deaths: EventWriter<DeathEvent>,
+) {
+    for event in damage.read() {
+        let Ok((mut health, shield, invulnerable)) = healths.get_mut(event.target) else {
+            continue;
+        };
+        // already dead, waiting to be despawned
+        if health.is_dead() || invulnerable {
+            continue;
+        }
+
+        let amount = match shield {
+            Some(mut shield) => shield.absorb(event.amount),
+            None => event.amount,
+        };
+        if amount > 0. {
+            health.current -= amount;
+        }
+
+        if health.is_dead() {
+            debug!("{:?} destroyed by {:?}", event.target, event.source);
+            deaths.send(DeathEvent {
+                entity: event.target,
+            });
+        }
+    }
+}
+
+// red bar under a ship, spawned as a child of the entity with `Health`
+pub fn health_bar(
+    half_width: f32,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) -> impl Bundle {
+    (
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Rectangle {
+                half_size: Vec2::new(half_width, 4.),
+            })),
+            material: materials.add(Color::RED),
+            transform: Transform {
+                translation: Vec3::new(0., -10., 0.),
+                scale: Vec3::splat(HEALTH_BAR_SCALE),
+                ..default()
+            },
+            ..default()
+        },
+        HealthBar,
+    )
+}
+
+fn tick_invulnerability(
+    mut invulnerables: Query<(Entity, &mut Invulnerable, Option<&mut Visibility>)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut invulnerable, visibility) in invulnerables.iter_mut() {
+        let over = invulnerable.0.tick(time.delta()).finished();
+        if over {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+
+        if let Some(mut visibility) = visibility {
+            let blinks = invulnerable.0.elapsed().as_millis() / BLINK_INTERVAL.as_millis();
+            let shown = if over || blinks % 2 == 1 {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            visibility.set_if_neq(shown);
+        }
+    }
+}
+
+fn recharge_shields(mut shields: Query<&mut Shield>, time: Res<Time>) {
+    for mut shield in shields.iter_mut() {
+        if shield.current < shield.max {
+            shield.current = (shield.current + shield.regen * time.delta_seconds()).min(shield.max);
+        }
+    }
+}
+
+// translucent circle around a ship, spawned as a child of the entity with `Shield`
+pub fn shield_bubble(
+    radius: f32,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+) -> impl Bundle {
+    (
+        MaterialMesh2dBundle {
+            mesh: Mesh2dHandle(meshes.add(Circle { radius })),
+            material: materials.add(Color::rgba(0.4, 0.7, 1., 0.3)),
+            transform: Transform::from_xyz(0., 0., 0.5),
+            ..default()
+        },
+        ShieldBubble,
+    )
+}
+
+fn sync_shield_bubbles(
+    mut bubbles: Query<(&Parent, &mut Visibility), With<ShieldBubble>>,
+    shields: Query<&Shield, Changed<Shield>>,
+) {
+    for (parent, mut visibility) in bubbles.iter_mut() {
+        if let Ok(shield) = shields.get(parent.get()) {
+            let shown = if shield.current > 0. {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            visibility.set_if_neq(shown);
+        }
+    }
+}
+
+fn sync_health_bars(
+    mut hp_bars: Query<(&Parent, &mut Transform), With<HealthBar>>,
+    healths: Query<&Health, Changed<Health>>,
+) {
+    for (parent, mut transform) in hp_bars.iter_mut() {
+        if let Ok(health) = healths.get(parent.get()) {
+            transform.scale.x = HEALTH_BAR_SCALE * health.current.max(0.) / health.max;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn death_is_sent_once_when_health_runs_out() {
+        let mut app = App::new();
+        app.add_plugins(HealthPlugin).init_resource::<Time>();
+
+        let source = app.world.spawn_empty().id();
+        let target = app.world.spawn(Health::new(3.)).id();
+        let bar = app
+            .world
+            .spawn((
+                HealthBar,
+                Transform::from_scale(Vec3::splat(HEALTH_BAR_SCALE)),
+            ))
+            .set_parent(target)
+            .id();
+
+        for amount in [1., 5., 1.] {
+            app.world.send_event(DamageEvent {
+                target,
+                amount,
+                source,
+            });
+        }
+        app.world.run_schedule(FixedUpdate);
+
+        assert!(app.world.get::<Health>(target).unwrap().is_dead());
+        assert_eq!(app.world.get::<Transform>(bar).unwrap().scale.x, 0.);
+
+        let deaths: Vec<_> = app
+            .world
+            .resource_mut::<Events<DeathEvent>>()
+            .drain()
+            .collect();
+        assert_eq!(deaths.len(), 1);
+        assert_eq!(deaths[0].entity, target);
+    }
+
+    #[test]
+    fn shield_takes_damage_first() {
+        let mut app = App::new();
+        app.add_plugins(HealthPlugin).init_resource::<Time>();
+
+        let source = app.world.spawn_empty().id();
+        let target = app.world.spawn((Health::new(3.), Shield::new(2., 1.))).id();
+
+        for amount in [1.5, 1.5] {
+            app.world.send_event(DamageEvent {
+                target,
+                amount,
+                source,
+            });
+        }
+        app.world.run_schedule(FixedUpdate);
+
+        assert_eq!(app.world.get::<Shield>(target).unwrap().current, 0.);
+        assert_eq!(app.world.get::<Health>(target).unwrap().current, 2.);
+    }
+
+    #[test]
+    fn invulnerable_entities_take_no_damage_until_it_wears_off() {
+        let mut app = App::new();
+        app.add_plugins(HealthPlugin).init_resource::<Time>();
+
+        let source = app.world.spawn_empty().id();
+        let target = app
+            .world
+            .spawn((Health::new(3.), Invulnerable::new(Duration::from_secs(1))))
+            .id();
+
+        let hit = |app: &mut App, elapsed| {
+            app.world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(elapsed));
+            app.world.send_event(DamageEvent {
+                target,
+                amount: 1.,
+                source,
+            });
+            app.world.run_schedule(FixedUpdate);
+            app.world.get::<Health>(target).unwrap().current
+        };
+
+        assert_eq!(hit(&mut app, 500), 3.);
+        assert_eq!(hit(&mut app, 250), 3.);
+        assert_eq!(hit(&mut app, 250), 2.);
+        assert!(app.world.get::<Invulnerable>(target).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    cmp::Reverse,
+    io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{load_ron, save_ron, DataDir};
+
+const FILE_NAME: &str = "highscores.ron";
+// number of entries kept in the table
+const MAX_ENTRIES: usize = 10;
+// longest name that can be entered on game over
+pub const MAX_NAME_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub points: u32,
+    pub kills: u32,
+}
+
+// Top scores sorted from best to worst
+#[derive(Resource, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    pub fn path(data_dir: &DataDir) -> Option<PathBuf> {
+        data_dir.file(FILE_NAME)
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        let mut scores: Self = load_ron(path, "high scores");
+        scores.sort();
+        scores
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        save_ron(self, path)
+    }
+
+    pub fn best(&self) -> Option<&HighScoreEntry> {
+        self.entries.first()
+    }
+
+    pub fn qualifies(&self, points: u32) -> bool {
+        points > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_some_and(|last| points > last.points))
+    }
+
+    // inserts the entry and returns its rank (0 is the best), if it made it into the table
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        if !self.qualifies(entry.points) {
+            return None;
+        }
+
+        // equal scores keep the older entry in front
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.points > other.points)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+
+        Some(rank)
+    }
+
+    fn sort(&mut self) {
+        self.entries.sort_by_key(|entry| Reverse(entry.points));
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    pub fn save(&self, data_dir: &DataDir) {
+        let Some(path) = Self::path(data_dir) else {
+            warn!("no data directory, high scores are not saved");
+            return;
+        };
+        if let Err(err) = self.save_to(&path) {
+            warn!("could not save high scores to {}: {}", path.display(), err);
+        }
+    }
+}
+
+// Loads the high scores from the `DataDir` inserted before it
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        let scores = HighScores::path(app.world.resource::<DataDir>())
+            .map(|path| HighScores::load_from(&path))
+            .unwrap_or_default();
+        app.insert_resource(scores);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    fn entry(name: &str, points: u32) -> HighScoreEntry {
+        HighScoreEntry {
+            name: name.into(),
+            points,
+            kills: points / 100,
+        }
+    }
+
+    fn temp_path(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rusty-invaders-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(FILE_NAME)
+    }
+
+    #[test]
+    fn keeps_the_best_ten_sorted() {
+        let mut scores = HighScores::default();
+        for points in 1..=12 {
+            scores.insert(entry("p", points * 100));
+        }
+
+        assert_eq!(scores.entries.len(), MAX_ENTRIES);
+        assert_eq!(scores.best().unwrap().points, 1200);
+        assert_eq!(scores.entries.last().unwrap().points, 300);
+        assert!(!scores.qualifies(300));
+        assert_eq!(scores.insert(entry("new", 650)), Some(6));
+    }
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let path = temp_path("round-trip");
+        let mut scores = HighScores::default();
+        scores.insert(entry("ana", 500));
+        scores.insert(entry("bo", 900));
+
+        scores.save_to(&path).unwrap();
+
+        assert_eq!(HighScores::load_from(&path), scores);
+    }
+
+    #[test]
+    fn missing_or_corrupt_files_load_empty() {
+        let path = temp_path("corrupt");
+        assert_eq!(HighScores::load_from(&path), HighScores::default());
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "(entries: [(name: ").unwrap();
+
+        assert_eq!(HighScores::load_from(&path), HighScores::default());
+        assert!(!path.exists());
+        assert!(path.with_extension("ron.corrupt").exists());
+    }
+}
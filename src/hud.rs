@@ -0,0 +1,329 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    boss::Boss, config::GameConfig, health::Health, player::Player, powerups::PowerUpEffects,
+    score::Score, waves::WaveStarted, weapon::Weapon, GameState, RoundEntity, RoundStart,
+    FONT_PATH,
+};
+
+// how long the wave banner stays on screen
+const WAVE_BANNER_DURATION: Duration = Duration::from_secs(2);
+
+#[derive(Component)]
+struct HPText;
+
+#[derive(Component)]
+struct EnemyCountText;
+
+// Weapon of the player and the time left on its power-up effects
+#[derive(Component)]
+struct WeaponText;
+
+#[derive(Component)]
+struct ScoreText;
+
+// "Wave N" text fading out at the start of a wave
+#[derive(Component)]
+struct WaveBanner(Timer);
+
+// Name and health of the boss across the top of the screen, while it's alive
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarFill;
+
+// Texts showing the state of the round
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(RoundStart, spawn_hud).add_systems(
+            Update,
+            (
+                update_hp_text,
+                update_weapon_text,
+                update_score_text.run_if(resource_changed::<Score>),
+                spawn_wave_banner,
+                fade_wave_banner,
+                (spawn_boss_bar, update_boss_bar, despawn_boss_bar).chain(),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn spawn_hud(config: Res<GameConfig>, asset_server: Res<AssetServer>, mut commands: Commands) {
+    let style = TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: 25.0,
+        ..default()
+    };
+
+    // enemies death count
+    commands.spawn((
+        TextBundle::from_section("enemies destroyed: 0", style.clone())
+            .with_text_justify(JustifyText::Center)
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                ..default()
+            }),
+        EnemyCountText,
+        RoundEntity,
+    ));
+
+    // score and combo multiplier
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new("score: 0", style.clone()),
+            TextSection::new(
+                "",
+                TextStyle {
+                    color: Color::hex("#FFD700").unwrap_or_default(),
+                    ..style.clone()
+                },
+            ),
+        ])
+        .with_text_justify(JustifyText::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        ScoreText,
+        RoundEntity,
+    ));
+
+    // hp, bombs and lives
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new("HP ", style.clone()),
+            TextSection::new(format!("{}", config.player_max_hp), style.clone()),
+            TextSection::new(format!("/{}", config.player_max_hp), style.clone()),
+            TextSection::new("   bombs ", style.clone()),
+            TextSection::new(format!("{}", config.player_bombs), style.clone()),
+            TextSection::new("   lives ", style.clone()),
+            TextSection::new(format!("{}", config.player_lives), style.clone()),
+        ])
+        .with_text_justify(JustifyText::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        HPText,
+        RoundEntity,
+    ));
+
+    // weapon and power-up effects, above the hp
+    commands.spawn((
+        TextBundle::from_section("blaster lv1", style).with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(45.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        WeaponText,
+        RoundEntity,
+    ));
+}
+
+fn update_hp_text(
+    mut hp_text: Query<&mut Text, With<HPText>>,
+    players: Query<(Ref<Health>, Ref<Player>)>,
+) {
+    for (health, player) in players.iter() {
+        if !health.is_changed() && !player.is_changed() {
+            continue;
+        }
+        for mut text in hp_text.iter_mut() {
+            text.sections[1].value = format!("{}", health.current.max(0.));
+            text.sections[4].value = format!("{}", player.bombs());
+            text.sections[6].value = format!("{}", player.lives());
+        }
+    }
+}
+
+fn update_weapon_text(
+    mut weapon_text: Query<&mut Text, With<WeaponText>>,
+    players: Query<(&Weapon, &PowerUpEffects)>,
+) {
+    for (weapon, effects) in players.iter() {
+        let mut value = format!("{} lv{}", weapon.kind.name(), weapon.level);
+        for (name, left) in [("rapid", effects.rapid_fire), ("shield", effects.shield)] {
+            if !left.is_zero() {
+                value += &format!("   {} {}s", name, left.as_secs_f32().ceil());
+            }
+        }
+
+        for mut text in weapon_text.iter_mut() {
+            if text.sections[0].value != value {
+                text.sections[0].value = value.clone();
+            }
+        }
+    }
+}
+
+fn update_score_text(
+    score: Res<Score>,
+    mut enemy_count_text: Query<&mut Text, (With<EnemyCountText>, Without<ScoreText>)>,
+    mut score_text: Query<&mut Text, With<ScoreText>>,
+) {
+    for mut text in enemy_count_text.iter_mut() {
+        text.sections[0].value = format!("enemies destroyed: {}", score.kills);
+    }
+
+    for mut text in score_text.iter_mut() {
+        text.sections[0].value = format!("score: {}", score.points);
+        text.sections[1].value = if score.multiplier > 1 {
+            format!(" x{}", score.multiplier)
+        } else {
+            String::new()
+        };
+    }
+}
+
+fn spawn_wave_banner(
+    mut started: EventReader<WaveStarted>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    for wave in started.read() {
+        let banner = if wave.boss {
+            "Boss incoming".into()
+        } else {
+            format!("Wave {}", wave.number)
+        };
+        commands.spawn((
+            TextBundle::from_section(
+                banner,
+                TextStyle {
+                    font: asset_server.load(FONT_PATH),
+                    font_size: 60.0,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(20.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..default()
+            })
+            .with_text_justify(JustifyText::Center),
+            WaveBanner(Timer::new(WAVE_BANNER_DURATION, TimerMode::Once)),
+            RoundEntity,
+        ));
+    }
+}
+
+fn fade_wave_banner(
+    mut banners: Query<(Entity, &mut WaveBanner, &mut Text)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut banner, mut text) in banners.iter_mut() {
+        if banner.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let alpha = 1. - banner.0.fraction();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+fn spawn_boss_bar(
+    bosses: Query<&Boss, Added<Boss>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    for boss in bosses.iter() {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(10.),
+                        left: Val::Percent(25.),
+                        width: Val::Percent(50.),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(6.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                BossBar,
+                RoundEntity,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    boss.name.clone(),
+                    TextStyle {
+                        font: asset_server.load(FONT_PATH),
+                        font_size: 20.0,
+                        ..default()
+                    },
+                ));
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Px(16.),
+                            border: UiRect::all(Val::Px(2.)),
+                            ..default()
+                        },
+                        border_color: Color::WHITE.into(),
+                        background_color: Color::rgba(0., 0., 0., 0.5).into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(100.),
+                                    height: Val::Percent(100.),
+                                    ..default()
+                                },
+                                background_color: Color::RED.into(),
+                                ..default()
+                            },
+                            BossBarFill,
+                        ));
+                    });
+            });
+    }
+}
+
+fn update_boss_bar(
+    bosses: Query<&Health, (With<Boss>, Changed<Health>)>,
+    mut fills: Query<&mut Style, With<BossBarFill>>,
+) {
+    for health in bosses.iter() {
+        for mut style in fills.iter_mut() {
+            style.width = Val::Percent(100. * health.current.max(0.) / health.max);
+        }
+    }
+}
+
+fn despawn_boss_bar(
+    bosses: Query<(), With<Boss>>,
+    bars: Query<Entity, With<BossBar>>,
+    mut commands: Commands,
+) {
+    if !bosses.is_empty() {
+        return;
+    }
+    for bar in bars.iter() {
+        commands.entity(bar).despawn_recursive();
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{settings::UserSettings, GameState, RoundStart};
+
+// Something the player can do, bound to keys and gamepad buttons
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Pause,
+    Bomb,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 7] = [
+        InputAction::MoveUp,
+        InputAction::MoveDown,
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::Fire,
+        InputAction::Pause,
+        InputAction::Bomb,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            InputAction::MoveUp => "move up",
+            InputAction::MoveDown => "move down",
+            InputAction::MoveLeft => "move left",
+            InputAction::MoveRight => "move right",
+            InputAction::Fire => "fire",
+            InputAction::Pause => "pause",
+            InputAction::Bomb => "bomb",
+        }
+    }
+}
+
+// Keys and gamepad buttons of every action, the left stick always moves
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Bindings {
+    pub keys: BTreeMap<InputAction, Vec<KeyCode>>,
+    pub buttons: BTreeMap<InputAction, Vec<GamepadButtonType>>,
+    // stick deflection (0 to 1) below which it counts as centered
+    pub deadzone: f32,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use GamepadButtonType::*;
+        use InputAction::*;
+
+        Self {
+            keys: BTreeMap::from([
+                (MoveUp, vec![KeyCode::KeyW, KeyCode::ArrowUp]),
+                (MoveDown, vec![KeyCode::KeyS, KeyCode::ArrowDown]),
+                (MoveLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+                (MoveRight, vec![KeyCode::KeyD, KeyCode::ArrowRight]),
+                (Fire, vec![KeyCode::Space]),
+                (Pause, vec![KeyCode::Escape]),
+                (Bomb, vec![KeyCode::KeyB]),
+            ]),
+            buttons: BTreeMap::from([
+                (MoveUp, vec![DPadUp]),
+                (MoveDown, vec![DPadDown]),
+                (MoveLeft, vec![DPadLeft]),
+                (MoveRight, vec![DPadRight]),
+                (Fire, vec![South, RightTrigger2]),
+                (Pause, vec![Start]),
+                (Bomb, vec![East, LeftTrigger2]),
+            ]),
+            deadzone: 0.3,
+        }
+    }
+}
+
+impl Bindings {
+    // makes `key` the first key of `action`, an action it's taken from gets the key it
+    // replaces so no action is left without a key
+    pub fn bind_key(&mut self, action: InputAction, key: KeyCode) {
+        bind(&mut self.keys, action, key);
+    }
+
+    pub fn bind_button(&mut self, action: InputAction, button: GamepadButtonType) {
+        bind(&mut self.buttons, action, button);
+    }
+
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn buttons(&self, action: InputAction) -> &[GamepadButtonType] {
+        self.buttons.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
+
+fn bind<T: PartialEq + Copy>(
+    map: &mut BTreeMap<InputAction, Vec<T>>,
+    action: InputAction,
+    input: T,
+) {
+    let replaced = map.get(&action).and_then(|inputs| inputs.first().copied());
+    if replaced == Some(input) {
+        return;
+    }
+
+    for (_, inputs) in map.iter_mut().filter(|(other, _)| **other != action) {
+        let Some(slot) = inputs.iter().position(|other| *other == input) else {
+            continue;
+        };
+        match replaced {
+            Some(replaced) if !inputs.contains(&replaced) => inputs[slot] = replaced,
+            _ => {
+                inputs.remove(slot);
+            }
+        }
+    }
+
+    let inputs = map.entry(action).or_default();
+    inputs.retain(|other| *other != input);
+    if inputs.is_empty() {
+        inputs.push(input);
+    } else {
+        inputs[0] = input;
+    }
+}
+
+// Actions held and newly pressed this frame, on the keyboard or any gamepad
+#[derive(Resource, Default)]
+pub struct Actions {
+    pressed: HashSet<InputAction>,
+    just_pressed: HashSet<InputAction>,
+    // left stick with the deadzone cut out, zero when it's centered
+    stick: Vec2,
+}
+
+impl Actions {
+    // the stick when it's pushed, otherwise the move actions
+    pub fn movement(&self) -> Vec2 {
+        if self.stick != Vec2::ZERO {
+            return self.stick.clamp_length_max(1.);
+        }
+
+        let axis = |negative, positive| {
+            self.pressed(positive) as i8 as f32 - self.pressed(negative) as i8 as f32
+        };
+        Vec2::new(
+            axis(InputAction::MoveLeft, InputAction::MoveRight),
+            axis(InputAction::MoveDown, InputAction::MoveUp),
+        )
+        .normalize_or_zero()
+    }
+
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+// Player input of a single simulation tick
+//
+// Gameplay only reads this resource, never the keyboard, so a tick plays out the
+// same whether the input comes from a human or from a replay.
+#[derive(Resource, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct PlayerInput {
+    // direction and strength the ship is steered with, kept as whole numbers so a
+    // replay repeats it exactly, see `movement`
+    pub move_x: i8,
+    pub move_y: i8,
+    // fire pressed or held since the last tick
+    pub fire: bool,
+    pub bomb: bool,
+}
+
+impl PlayerInput {
+    pub fn moving(movement: Vec2) -> Self {
+        let movement = (movement.clamp_length_max(1.) * AXIS_MAX).round();
+        Self {
+            move_x: movement.x as i8,
+            move_y: movement.y as i8,
+            ..default()
+        }
+    }
+
+    // never longer than 1, so diagonals aren't any faster
+    pub fn movement(&self) -> Vec2 {
+        (Vec2::new(self.move_x as f32, self.move_y as f32) / AXIS_MAX).clamp_length_max(1.)
+    }
+
+    pub fn to_bytes(self) -> [u8; 3] {
+        let buttons = self.fire as u8 | (self.bomb as u8) << 1;
+        [buttons, self.move_x as u8, self.move_y as u8]
+    }
+
+    pub fn from_bytes([buttons, move_x, move_y]: [u8; 3]) -> Self {
+        Self {
+            move_x: move_x as i8,
+            move_y: move_y as i8,
+            fire: buttons & 1 != 0,
+            bomb: buttons & 2 != 0,
+        }
+    }
+}
+
+// Input collected every frame until the next tick picks it up
+//
+// A press of fire or bomb is kept until a tick has seen it, so a short tap between
+// two ticks isn't lost. Fire also stays set while it's held down.
+#[derive(Resource, Default)]
+pub struct PendingInput(PlayerInput);
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputSampling;
+
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Actions>()
+            .init_resource::<PlayerInput>()
+            .init_resource::<PendingInput>()
+            .add_systems(
+                PreUpdate,
+                (
+                    update_actions,
+                    collect_input.run_if(in_state(GameState::Playing)),
+                )
+                    .chain()
+                    .after(InputSystem),
+            )
+            .add_systems(RoundStart, reset_input)
+            .add_systems(FixedUpdate, sample_input.in_set(InputSampling));
+    }
+}
+
+// largest value of `PlayerInput::move_x` and `move_y`
+const AXIS_MAX: f32 = i8::MAX as f32;
+
+// rescales the stick so it starts from zero right at the edge of the deadzone
+fn apply_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+    let strength = ((length - deadzone) / (1. - deadzone).max(f32::EPSILON)).min(1.);
+    stick / length * strength
+}
+
+fn update_actions(
+    settings: Res<UserSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut actions: ResMut<Actions>,
+) {
+    let bindings = &settings.controls;
+    let was_pressed = std::mem::take(&mut actions.pressed);
+    actions.just_pressed.clear();
+
+    for action in InputAction::ALL {
+        let keys_bound = bindings.keys(action);
+        let buttons_bound: Vec<_> = gamepads
+            .iter()
+            .flat_map(|gamepad| {
+                bindings
+                    .buttons(action)
+                    .iter()
+                    .map(move |button| GamepadButton::new(gamepad, *button))
+            })
+            .collect();
+
+        if keys.any_pressed(keys_bound.iter().copied())
+            || buttons.any_pressed(buttons_bound.iter().copied())
+        {
+            actions.pressed.insert(action);
+        }
+        if keys.any_just_pressed(keys_bound.iter().copied())
+            || buttons.any_just_pressed(buttons_bound)
+        {
+            actions.just_pressed.insert(action);
+        }
+    }
+
+    // the gamepad pushed the furthest wins
+    actions.stick = gamepads
+        .iter()
+        .map(|gamepad| {
+            let axis = |axis_type| axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or(0.);
+            let stick = Vec2::new(
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            );
+            apply_deadzone(stick, bindings.deadzone)
+        })
+        .fold(Vec2::ZERO, |furthest, stick| {
+            if stick.length() > furthest.length() {
+                stick
+            } else {
+                furthest
+            }
+        });
+
+    let Actions {
+        pressed,
+        just_pressed,
+        ..
+    } = actions.as_mut();
+    just_pressed.extend(pressed.difference(&was_pressed));
+}
+
+fn collect_input(actions: Res<Actions>, mut pending: ResMut<PendingInput>) {
+    let pending = &mut pending.0;
+    let steering = PlayerInput::moving(actions.movement());
+    pending.move_x = steering.move_x;
+    pending.move_y = steering.move_y;
+    pending.fire |= actions.pressed(InputAction::Fire) || actions.just_pressed(InputAction::Fire);
+    pending.bomb |= actions.just_pressed(InputAction::Bomb);
+}
+
+// a press left over from the previous round doesn't fire or bomb in the new one
+fn reset_input(mut pending: ResMut<PendingInput>, mut input: ResMut<PlayerInput>) {
+    *pending = PendingInput::default();
+    *input = PlayerInput::default();
+}
+
+pub fn sample_input(mut pending: ResMut<PendingInput>, mut input: ResMut<PlayerInput>) {
+    *input = pending.0;
+    pending.0.fire = false;
+    pending.0.bomb = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_round_trips_through_bytes() {
+        let input = PlayerInput {
+            move_x: -127,
+            move_y: 90,
+            fire: true,
+            bomb: false,
+        };
+
+        assert_eq!(PlayerInput::from_bytes(input.to_bytes()), input);
+    }
+
+    #[test]
+    fn diagonal_movement_is_not_faster() {
+        let diagonal = PlayerInput::moving(Vec2::ONE.normalize()).movement();
+        let pushed_too_far = PlayerInput::moving(Vec2::new(1., 1.)).movement();
+
+        assert!((diagonal.length() - 1.).abs() < 1e-2);
+        assert!(pushed_too_far.length() <= 1.);
+    }
+
+    #[test]
+    fn binding_a_key_swaps_it_with_the_other_action() {
+        let mut bindings = Bindings::default();
+
+        bindings.bind_key(InputAction::Fire, KeyCode::KeyW);
+
+        assert_eq!(bindings.keys(InputAction::Fire), &[KeyCode::KeyW]);
+        assert_eq!(
+            bindings.keys(InputAction::MoveUp),
+            &[KeyCode::Space, KeyCode::ArrowUp]
+        );
+    }
+
+    #[test]
+    fn every_action_keeps_a_binding_after_a_rebind() {
+        let defaults = Bindings::default();
+        for action in InputAction::ALL {
+            for other in InputAction::ALL {
+                let mut bindings = defaults.clone();
+                for key in defaults.keys(other) {
+                    bindings.bind_key(action, *key);
+                }
+                for button in defaults.buttons(other) {
+                    bindings.bind_button(action, *button);
+                }
+
+                for action in InputAction::ALL {
+                    assert!(!bindings.keys(action).is_empty(), "{:?} has no key", action);
+                    assert!(!bindings.buttons(action).is_empty());
+                }
+            }
+        }
+
+        let mut bindings = defaults;
+        bindings.bind_key(InputAction::Fire, KeyCode::Escape);
+        assert_eq!(bindings.keys(InputAction::Pause), &[KeyCode::Space]);
+    }
+
+    #[test]
+    fn presses_outside_a_round_are_ignored() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, PlayerInputPlugin))
+            .init_state::<GameState>()
+            .init_resource::<UserSettings>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Axis<GamepadAxis>>();
+        let pending = |app: &App| app.world.resource::<PendingInput>().0;
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyB);
+        app.update();
+        assert!(!pending(&app).bomb);
+
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        let mut keys = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        keys.clear();
+        keys.press(KeyCode::Space);
+        // the state changes after the input is collected
+        app.update();
+        app.update();
+        assert!(pending(&app).fire);
+        assert!(!pending(&app).bomb);
+    }
+
+    #[test]
+    fn stick_inside_the_deadzone_does_nothing() {
+        assert_eq!(apply_deadzone(Vec2::new(0.2, -0.1), 0.3), Vec2::ZERO);
+
+        let half = apply_deadzone(Vec2::new(0.65, 0.), 0.3);
+        assert!((half - Vec2::new(0.5, 0.)).length() < 1e-5);
+        assert_eq!(apply_deadzone(Vec2::new(0., -1.), 0.3), Vec2::new(0., -1.));
+    }
+}
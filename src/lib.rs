@@ -0,0 +1,397 @@
+pub mod archetypes;
+pub mod audio;
+pub mod boss;
+pub mod collision;
+pub mod combat;
+pub mod config;
+pub mod effects;
+pub mod enemy;
+pub mod health;
+pub mod highscores;
+pub mod hud;
+pub mod input;
+pub mod menu;
+pub mod movement;
+pub mod music;
+pub mod patterns;
+pub mod player;
+pub mod powerups;
+pub mod projectile;
+pub mod replay;
+pub mod rng;
+pub mod score;
+pub mod settings;
+pub mod storage;
+pub mod view;
+pub mod waves;
+pub mod weapon;
+
+use std::path::PathBuf;
+
+use archetypes::{archetypes_settled, ArchetypePlugin};
+use bevy::{
+    app::AppExit,
+    ecs::schedule::{ExecutorKind, ScheduleLabel},
+    prelude::*,
+    window::ExitCondition,
+};
+use boss::BossPlugin;
+use collision::{CollisionDetection, CollisionPlugin};
+use combat::CombatPlugin;
+use config::{config_settled, ConfigPlugin};
+use effects::EffectsPlugin;
+use enemy::EnemyPlugin;
+use health::{HealthPlugin, HealthUpdate};
+use highscores::HighScoresPlugin;
+use hud::HudPlugin;
+use input::{InputSampling, PlayerInputPlugin};
+use menu::MenuPlugin;
+use music::MusicPlugin;
+use player::PlayerPlugin;
+use powerups::PowerUpPlugin;
+use projectile::ProjectilePlugin;
+use replay::{save_recording, start_recording, Playback, ReplayMode, ReplayPlugin};
+use rng::{reseed_rng, RngPlugin};
+use score::{Score, ScorePlugin, ScoreUpdate};
+use settings::SettingsPlugin;
+use storage::DataDir;
+use view::ViewPlugin;
+use waves::{waves_settled, WaveDirector, WavePlugin, WaveSpawning};
+
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Controls,
+    Playing,
+    Paused,
+    GameOver,
+    // the boss is beaten
+    Victory,
+}
+
+// Where the game takes place, in world units
+//
+// Gameplay only reads this resource, never the window, so the size of the window
+// doesn't change how a round plays out. Insert one before `GamePlugin` to change it.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct PlayArea {
+    // visible part of the world
+    pub bounds: Rect,
+    // part of `bounds` the player ship has to stay inside
+    pub player_zone: Rect,
+    // how far outside of `bounds` enemies and projectiles go before they are despawned
+    pub despawn_margin: f32,
+}
+
+impl Default for PlayArea {
+    fn default() -> Self {
+        let bounds = Rect::from_center_size(Vec2::ZERO, Vec2::new(1920., 1080.));
+        Self {
+            bounds,
+            // the left half
+            player_zone: Rect::new(
+                bounds.min.x + 10.,
+                bounds.min.y + 10.,
+                -10.,
+                bounds.max.y - 10.,
+            ),
+            despawn_margin: 100.,
+        }
+    }
+}
+
+impl PlayArea {
+    // things leaving this rect are despawned
+    pub fn despawn_bounds(&self) -> Rect {
+        let margin = Vec2::splat(self.despawn_margin);
+        Rect::from_corners(self.bounds.min - margin, self.bounds.max + margin)
+    }
+}
+
+// Stands in for the parts of `DefaultPlugins` the game needs, for running it without
+// a window or renderer
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            AssetPlugin {
+                watch_for_changes_override: Some(false),
+                ..default()
+            },
+            bevy::input::InputPlugin,
+            WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            },
+        ))
+        .init_asset::<Image>()
+        .init_asset::<Font>()
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .init_asset::<AudioSource>()
+        .init_resource::<UiScale>();
+    }
+}
+
+// Everything that belongs to a single round and is despawned when it ends
+#[derive(Component)]
+pub struct RoundEntity;
+
+// Schedule run once at the start of every round, after the rng is seeded
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RoundStart;
+
+// Steps of a fixed tick, in the order they run
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TickSet {
+    // turns the input of the tick into intents, ticks cooldowns
+    Prepare,
+    Spawn,
+    Shoot,
+    Move,
+    // reacts to the collisions of the tick
+    Resolve,
+    // particles and other feedback, while whatever died is still around
+    Effects,
+    // despawns whatever died
+    Cleanup,
+}
+
+// gameplay settings live in `assets/config.ron`, see `config::GameConfig`
+//
+// size of the ship sprites in pixels and the scale they are drawn with
+pub const SHIP_SPRITE_SIZE: Vec2 = Vec2::new(16., 16.);
+pub const SHIP_SCALE: f32 = 6.5;
+pub const FONT_PATH: &str = "fonts/Quinquefive-ALoRM.ttf";
+
+// Everything of the game except the window, runs under `MinimalPlugins` together
+// with `HeadlessPlugin`
+#[derive(Default)]
+pub struct GamePlugin {
+    // seed of every round, see `rng::RngPlugin`
+    pub seed: Option<u64>,
+    // a replay brings its own seed and skips the main menu
+    pub replay: ReplayMode,
+    // start a round right away instead of showing the main menu
+    pub skip_menu: bool,
+    // where high scores and settings are kept, see `storage::data_dir`
+    pub data_dir: Option<PathBuf>,
+}
+
+// Marks an app started with `GamePlugin::skip_menu`
+#[derive(Resource)]
+struct SkipMenu;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        let seed = match &self.replay {
+            ReplayMode::Play(recording) => Some(recording.seed),
+            _ => self.seed,
+        };
+        if self.skip_menu || matches!(self.replay, ReplayMode::Play(_)) {
+            app.insert_resource(SkipMenu);
+        }
+        // read by the plugins loading the high scores and settings
+        app.insert_resource(DataDir(self.data_dir.clone()));
+
+        app.add_plugins((
+            ConfigPlugin,
+            CollisionPlugin,
+            HealthPlugin,
+            ScorePlugin,
+            HighScoresPlugin,
+            SettingsPlugin,
+            WavePlugin,
+            ArchetypePlugin,
+            RngPlugin { seed },
+            PlayerInputPlugin,
+            ReplayPlugin {
+                mode: self.replay.clone(),
+            },
+        ))
+        .add_plugins((
+            PlayerPlugin,
+            EnemyPlugin,
+            BossPlugin,
+            ProjectilePlugin,
+            PowerUpPlugin,
+            CombatPlugin,
+            EffectsPlugin,
+            HudPlugin,
+            audio::AudioPlugin,
+            MusicPlugin,
+            MenuPlugin,
+            ViewPlugin,
+        ))
+        .init_state::<GameState>()
+        .init_schedule(RoundStart)
+        // keeps the entities of a round spawned in the same order
+        .edit_schedule(RoundStart, |schedule| {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        })
+        .add_systems(RoundStart, reset_round)
+        .add_systems(OnEnter(GameState::MainMenu), despawn_all::<RoundEntity>)
+        .add_systems(
+            OnTransition {
+                from: GameState::MainMenu,
+                to: GameState::Playing,
+            },
+            (reseed_rng, start_recording, run_round_start).chain(),
+        )
+        .add_systems(
+            OnTransition {
+                from: GameState::GameOver,
+                to: GameState::Playing,
+            },
+            (
+                despawn_all::<RoundEntity>,
+                reseed_rng,
+                start_recording,
+                run_round_start,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnTransition {
+                from: GameState::Victory,
+                to: GameState::Playing,
+            },
+            (
+                despawn_all::<RoundEntity>,
+                reseed_rng,
+                start_recording,
+                run_round_start,
+            )
+                .chain(),
+        )
+        .add_systems(OnEnter(GameState::Paused), pause_time)
+        .add_systems(OnExit(GameState::Paused), resume_time)
+        .add_systems(
+            OnTransition {
+                from: GameState::Paused,
+                to: GameState::MainMenu,
+            },
+            save_recording,
+        )
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            (save_recording, finish_replay),
+        )
+        .add_systems(OnEnter(GameState::Victory), (save_recording, finish_replay))
+        .add_systems(
+            Update,
+            finish_replay.run_if(in_state(GameState::Playing).and_then(replay_ran_out)),
+        )
+        // quitting or closing the window in the middle of a round keeps its recording
+        .add_systems(
+            Last,
+            save_recording.run_if(
+                on_event::<AppExit>()
+                    .and_then(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+            ),
+        )
+        .add_systems(
+            Update,
+            skip_main_menu.run_if(
+                in_state(GameState::MainMenu)
+                    .and_then(resource_exists::<SkipMenu>)
+                    .and_then(config_settled)
+                    .and_then(waves_settled)
+                    .and_then(archetypes_settled),
+            ),
+        )
+        // the round is simulated in fixed ticks, so a seed and the input of every tick
+        // always play out the same way
+        .configure_sets(
+            FixedUpdate,
+            (
+                InputSampling,
+                TickSet::Prepare,
+                WaveSpawning,
+                TickSet::Spawn,
+                TickSet::Shoot,
+                TickSet::Move,
+                CollisionDetection,
+                TickSet::Resolve,
+                HealthUpdate,
+                ScoreUpdate,
+                TickSet::Effects,
+                TickSet::Cleanup,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .init_resource::<PlayArea>();
+    }
+}
+
+fn run_round_start(world: &mut World) {
+    world.run_schedule(RoundStart);
+}
+
+fn reset_round(mut score: ResMut<Score>, mut director: ResMut<WaveDirector>) {
+    *score = Score::default();
+    director.reset();
+}
+
+pub(crate) fn despawn_all<T: Component>(entities: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// waits for the settings to be loaded, so a replay plays with the ones it was recorded with
+fn skip_main_menu(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+fn replay_ran_out(playback: Option<Res<Playback>>) -> bool {
+    playback.is_some_and(|playback| playback.ran_out())
+}
+
+fn finish_replay(
+    playback: Option<Res<Playback>>,
+    score: Res<Score>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if let Some(playback) = playback {
+        info!(
+            "replay finished after {} ticks with {} points and {} kills",
+            playback.ticks(),
+            score.points,
+            score.kills
+        );
+        exit.send(AppExit);
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+#[cfg(test)]
+fn test_app<M>(frame_time: std::time::Duration, systems: impl IntoSystemConfigs<M>) -> App {
+    use bevy::time::TimeUpdateStrategy;
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<config::GameConfig>()
+        .init_resource::<PlayArea>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(frame_time))
+        .add_systems(Update, systems);
+    // the first update only starts the clock
+    app.update();
+    app
+}
+
+#[cfg(test)]
+fn x_of(app: &App, entity: Entity) -> f32 {
+    app.world.get::<Transform>(entity).unwrap().translation.x
+}
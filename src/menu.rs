@@ -0,0 +1,743 @@
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*, window::ReceivedCharacter};
+
+use crate::{
+    despawn_all,
+    highscores::{HighScoreEntry, HighScores, MAX_NAME_LEN},
+    input::{Actions, Bindings, InputAction},
+    score::Score,
+    settings::{UserSettings, VolumeChannel},
+    storage::DataDir,
+    GameState, FONT_PATH,
+};
+
+#[derive(Component)]
+struct MainMenuScreen;
+
+#[derive(Component)]
+struct PauseScreen;
+
+#[derive(Component)]
+struct GameOverScreen;
+
+#[derive(Component)]
+struct ControlsScreen;
+
+#[derive(Component)]
+struct NameEntryText;
+
+// text of a row on the controls screen
+#[derive(Component)]
+struct BindingText(InputAction);
+
+// text of a volume row on the controls screen
+#[derive(Component)]
+struct VolumeText(VolumeChannel);
+
+// action waiting for the key or gamepad button it gets bound to
+#[derive(Resource)]
+struct Rebinding(InputAction);
+
+// name typed on the game over screen while the score qualifies for the high scores
+#[derive(Resource, Default)]
+struct NameEntry {
+    name: String,
+}
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    Start,
+    Resume,
+    Restart,
+    MainMenu,
+    Controls,
+    Rebind(InputAction),
+    ResetControls,
+    // raises the volume, full volume goes back to muted
+    Volume(VolumeChannel),
+    Quit,
+}
+
+const BUTTON_COLOR: &str = "#202020";
+const BUTTON_HOVERED_COLOR: &str = "#404040";
+
+// Main menu, pause, game over and victory screens, and the name entry for high scores
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), despawn_all::<MainMenuScreen>)
+            .add_systems(OnEnter(GameState::Controls), spawn_controls_screen)
+            .add_systems(
+                OnExit(GameState::Controls),
+                (despawn_all::<ControlsScreen>, stop_rebinding),
+            )
+            .add_systems(OnEnter(GameState::Paused), spawn_pause_screen)
+            .add_systems(OnExit(GameState::Paused), despawn_all::<PauseScreen>)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (start_name_entry, spawn_game_over_screen).chain(),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                (finish_name_entry, despawn_all::<GameOverScreen>),
+            )
+            .add_systems(
+                OnEnter(GameState::Victory),
+                (start_name_entry, spawn_victory_screen).chain(),
+            )
+            .add_systems(
+                OnExit(GameState::Victory),
+                (finish_name_entry, despawn_all::<GameOverScreen>),
+            )
+            .add_systems(
+                Update,
+                (
+                    // the enter that submits a name mustn't restart the round as well
+                    menu_keyboard_input.before(name_entry_input),
+                    menu_button_system,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    rebind_input.after(menu_keyboard_input),
+                    update_binding_texts,
+                    update_volume_texts,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Controls)),
+            )
+            .add_systems(
+                Update,
+                (name_entry_input, submit_name, update_name_entry_text)
+                    .chain()
+                    .run_if(in_state(GameState::GameOver).or_else(in_state(GameState::Victory))),
+            );
+    }
+}
+
+// Enter, or start on a gamepad
+//
+// Doesn't go through the pause action, escape is bound to it by default.
+#[derive(SystemParam)]
+struct Confirm<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+}
+
+impl Confirm<'_> {
+    fn just_pressed(&self) -> bool {
+        self.keys.just_pressed(KeyCode::Enter)
+            || self
+                .buttons
+                .get_just_pressed()
+                .any(|button| button.button_type == GamepadButtonType::Start)
+    }
+}
+
+// confirming starts a round, the pause action pauses and resumes it
+fn menu_keyboard_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    confirm: Confirm,
+    actions: Res<Actions>,
+    state: Res<State<GameState>>,
+    name_entry: Option<Res<NameEntry>>,
+    rebinding: Option<Res<Rebinding>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let confirm = confirm.just_pressed();
+    match state.get() {
+        GameState::MainMenu => {
+            if confirm {
+                next_state.set(GameState::Playing);
+            }
+        }
+        GameState::Controls => {
+            // escape cancels a rebinding first
+            if keys.just_pressed(KeyCode::Escape) && rebinding.is_none() {
+                next_state.set(GameState::MainMenu);
+            }
+        }
+        GameState::GameOver | GameState::Victory => {
+            // confirming submits the name first
+            if confirm && name_entry.is_none() {
+                next_state.set(GameState::Playing);
+            }
+        }
+        GameState::Playing => {
+            if actions.just_pressed(InputAction::Pause) {
+                next_state.set(GameState::Paused);
+            }
+        }
+        GameState::Paused => {
+            if actions.just_pressed(InputAction::Pause) {
+                next_state.set(GameState::Playing);
+            }
+        }
+    }
+}
+
+fn menu_button_system(
+    mut buttons: Query<(&Interaction, &MenuButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut settings: ResMut<UserSettings>,
+    data_dir: Res<DataDir>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    for (interaction, button, mut background) in buttons.iter_mut() {
+        match interaction {
+            Interaction::Pressed => match button {
+                MenuButton::Start | MenuButton::Resume | MenuButton::Restart => {
+                    next_state.set(GameState::Playing)
+                }
+                MenuButton::MainMenu => next_state.set(GameState::MainMenu),
+                MenuButton::Controls => next_state.set(GameState::Controls),
+                MenuButton::Rebind(action) => commands.insert_resource(Rebinding(*action)),
+                MenuButton::ResetControls => {
+                    settings.controls = Bindings::default();
+                    settings.save(&data_dir);
+                }
+                MenuButton::Volume(channel) => {
+                    settings.volume.step(*channel);
+                    settings.save(&data_dir);
+                }
+                MenuButton::Quit => {
+                    exit.send(AppExit);
+                }
+            },
+            Interaction::Hovered => {
+                *background = Color::hex(BUTTON_HOVERED_COLOR).unwrap_or_default().into();
+            }
+            Interaction::None => {
+                *background = Color::hex(BUTTON_COLOR).unwrap_or_default().into();
+            }
+        }
+    }
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+) {
+    spawn_screen(
+        &mut commands,
+        &asset_server,
+        MainMenuScreen,
+        "RUSTY INVADERS",
+        |parent, font| {
+            let best = match high_scores.best() {
+                Some(best) => format!("best: {} by {}", best.points, best.name),
+                None => "no high scores yet".into(),
+            };
+            parent.spawn(menu_text(font, best, 30.));
+
+            for (rank, entry) in high_scores.entries.iter().enumerate() {
+                parent.spawn(menu_text(
+                    font,
+                    format!(
+                        "{:>2}. {:<width$} {:>8}",
+                        rank + 1,
+                        entry.name,
+                        entry.points,
+                        width = MAX_NAME_LEN
+                    ),
+                    18.,
+                ));
+            }
+        },
+        &[
+            (MenuButton::Start, "start"),
+            (MenuButton::Controls, "controls"),
+            (MenuButton::Quit, "quit"),
+        ],
+    );
+}
+
+fn spawn_controls_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<UserSettings>,
+) {
+    spawn_screen(
+        &mut commands,
+        &asset_server,
+        ControlsScreen,
+        "CONTROLS",
+        |parent, font| {
+            parent.spawn(menu_text(
+                font,
+                "click an action, then press a key or button, click a volume to raise it",
+                18.,
+            ));
+
+            for action in InputAction::ALL {
+                parent
+                    .spawn((button_bundle(1000., 50.), MenuButton::Rebind(action)))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            menu_text(font, binding_label(action, &settings.controls), 18.),
+                            BindingText(action),
+                        ));
+                    });
+            }
+
+            for channel in VolumeChannel::ALL {
+                parent
+                    .spawn((button_bundle(1000., 50.), MenuButton::Volume(channel)))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            menu_text(font, volume_label(channel, &settings), 18.),
+                            VolumeText(channel),
+                        ));
+                    });
+            }
+        },
+        &[
+            (MenuButton::ResetControls, "reset"),
+            (MenuButton::MainMenu, "back"),
+        ],
+    );
+}
+
+fn binding_label(action: InputAction, bindings: &Bindings) -> String {
+    let keys: Vec<_> = bindings
+        .keys(action)
+        .iter()
+        .map(|key| format!("{:?}", key).trim_start_matches("Key").to_owned())
+        .collect();
+    let buttons: Vec<_> = bindings
+        .buttons(action)
+        .iter()
+        .map(|button| format!("{:?}", button))
+        .collect();
+
+    format!(
+        "{}: {} / {}",
+        action.label(),
+        keys.join(", "),
+        buttons.join(", ")
+    )
+}
+
+fn volume_label(channel: VolumeChannel, settings: &UserSettings) -> String {
+    format!(
+        "{}: {}%",
+        channel.label(),
+        (settings.volume.get(channel) * 100.).round()
+    )
+}
+
+// binds the next key or gamepad button pressed, escape cancels
+fn rebind_input(
+    rebinding: Option<Res<Rebinding>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    mut settings: ResMut<UserSettings>,
+    data_dir: Res<DataDir>,
+    mut commands: Commands,
+) {
+    let Some(rebinding) = rebinding else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::Escape) {
+        // keeps the old binding
+    } else if let Some(key) = keys.get_just_pressed().next() {
+        settings.controls.bind_key(rebinding.0, *key);
+        settings.save(&data_dir);
+    } else if let Some(button) = buttons.get_just_pressed().next() {
+        settings
+            .controls
+            .bind_button(rebinding.0, button.button_type);
+        settings.save(&data_dir);
+    } else {
+        return;
+    }
+
+    commands.remove_resource::<Rebinding>();
+}
+
+fn update_binding_texts(
+    rebinding: Option<Res<Rebinding>>,
+    settings: Res<UserSettings>,
+    mut texts: Query<(&mut Text, &BindingText)>,
+) {
+    for (mut text, BindingText(action)) in texts.iter_mut() {
+        let label = match &rebinding {
+            Some(rebinding) if rebinding.0 == *action => {
+                format!("{}: press a key or button...", action.label())
+            }
+            _ => binding_label(*action, &settings.controls),
+        };
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}
+
+fn update_volume_texts(settings: Res<UserSettings>, mut texts: Query<(&mut Text, &VolumeText)>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut text, VolumeText(channel)) in texts.iter_mut() {
+        text.sections[0].value = volume_label(*channel, &settings);
+    }
+}
+
+fn stop_rebinding(mut commands: Commands) {
+    commands.remove_resource::<Rebinding>();
+}
+
+fn spawn_pause_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_screen(
+        &mut commands,
+        &asset_server,
+        PauseScreen,
+        "PAUSED",
+        |_, _| {},
+        &[
+            (MenuButton::Resume, "resume"),
+            (MenuButton::MainMenu, "main menu"),
+            (MenuButton::Quit, "quit"),
+        ],
+    );
+}
+
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    name_entry: Option<Res<NameEntry>>,
+) {
+    let name_entry = name_entry.is_some();
+    spawn_results_screen(
+        &mut commands,
+        &asset_server,
+        &score,
+        name_entry,
+        "GAME OVER",
+    );
+}
+
+fn spawn_victory_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    score: Res<Score>,
+    name_entry: Option<Res<NameEntry>>,
+) {
+    let name_entry = name_entry.is_some();
+    spawn_results_screen(&mut commands, &asset_server, &score, name_entry, "VICTORY");
+}
+
+// score of the round, the name entry and what to do next
+fn spawn_results_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    score: &Score,
+    name_entry: bool,
+    title: &str,
+) {
+    spawn_screen(
+        commands,
+        asset_server,
+        GameOverScreen,
+        title,
+        |parent, font| {
+            parent.spawn(menu_text(
+                font,
+                format!(
+                    "score: {}   enemies destroyed: {}",
+                    score.points, score.kills
+                ),
+                25.,
+            ));
+
+            if name_entry {
+                parent.spawn(menu_text(font, "new high score! type your name", 25.));
+                parent.spawn((menu_text(font, "", 30.), NameEntryText));
+            }
+        },
+        &[
+            (MenuButton::Restart, "restart"),
+            (MenuButton::MainMenu, "main menu"),
+            (MenuButton::Quit, "quit"),
+        ],
+    );
+}
+
+fn menu_text(font: &Handle<Font>, value: impl Into<String>, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        value,
+        TextStyle {
+            font: font.clone(),
+            font_size,
+            ..default()
+        },
+    )
+}
+
+// full screen overlay with a title, some content and a column of buttons
+fn spawn_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    marker: impl Component,
+    title: &str,
+    content: impl FnOnce(&mut ChildBuilder, &Handle<Font>),
+    buttons: &[(MenuButton, &str)],
+) {
+    let font = asset_server.load(FONT_PATH);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(20.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.75).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    title,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 60.0,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(40.)),
+                    ..default()
+                }),
+            );
+
+            content(parent, &font);
+
+            for (button, label) in buttons {
+                parent
+                    .spawn((button_bundle(400., 70.), *button))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            *label,
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 25.0,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn button_bundle(width: f32, height: f32) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            width: Val::Px(width),
+            height: Val::Px(height),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: Color::hex(BUTTON_COLOR).unwrap_or_default().into(),
+        ..default()
+    }
+}
+
+fn start_name_entry(
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    mut characters: ResMut<Events<ReceivedCharacter>>,
+    mut commands: Commands,
+) {
+    if high_scores.qualifies(score.points) {
+        // don't start the name with whatever was typed while playing
+        characters.clear();
+        commands.init_resource::<NameEntry>();
+    }
+}
+
+fn name_entry_input(
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<ButtonInput<KeyCode>>,
+    name_entry: Option<ResMut<NameEntry>>,
+) {
+    let Some(mut name_entry) = name_entry else {
+        characters.clear();
+        return;
+    };
+
+    for event in characters.read() {
+        for char in event.char.chars() {
+            if (char.is_alphanumeric() || char == ' ' || char == '-' || char == '_')
+                && name_entry.name.chars().count() < MAX_NAME_LEN
+            {
+                name_entry.name.push(char);
+            }
+        }
+    }
+    if keys.just_pressed(KeyCode::Backspace) {
+        name_entry.name.pop();
+    }
+}
+
+fn submit_name(
+    confirm: Confirm,
+    name_entry: Option<Res<NameEntry>>,
+    score: Res<Score>,
+    mut high_scores: ResMut<HighScores>,
+    data_dir: Res<DataDir>,
+    mut texts: Query<&mut Text, With<NameEntryText>>,
+    mut commands: Commands,
+) {
+    let Some(name_entry) = name_entry else {
+        return;
+    };
+
+    if confirm.just_pressed() {
+        let rank = submit_high_score(&name_entry.name, &score, &mut high_scores, &data_dir);
+        commands.remove_resource::<NameEntry>();
+
+        for mut text in texts.iter_mut() {
+            text.sections[0].value = match rank {
+                Some(rank) => format!("saved as #{}", rank + 1),
+                None => String::new(),
+            };
+        }
+    }
+}
+
+fn update_name_entry_text(
+    name_entry: Option<Res<NameEntry>>,
+    mut texts: Query<&mut Text, With<NameEntryText>>,
+) {
+    let Some(name_entry) = name_entry.filter(|name_entry| name_entry.is_changed()) else {
+        return;
+    };
+
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = format!("{}_", name_entry.name);
+    }
+}
+
+// leaving the results screen without confirming still keeps the score
+fn finish_name_entry(
+    name_entry: Option<Res<NameEntry>>,
+    score: Res<Score>,
+    mut high_scores: ResMut<HighScores>,
+    data_dir: Res<DataDir>,
+    mut commands: Commands,
+) {
+    if let Some(name_entry) = name_entry {
+        submit_high_score(&name_entry.name, &score, &mut high_scores, &data_dir);
+        commands.remove_resource::<NameEntry>();
+    }
+}
+
+fn submit_high_score(
+    name: &str,
+    score: &Score,
+    high_scores: &mut HighScores,
+    data_dir: &DataDir,
+) -> Option<usize> {
+    let name = name.trim();
+    let rank = high_scores.insert(HighScoreEntry {
+        name: if name.is_empty() { "PLAYER" } else { name }.into(),
+        points: score.points,
+        kills: score.kills,
+    });
+    high_scores.save(data_dir);
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::PlayerInputPlugin;
+
+    fn menu_app(state: GameState) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, PlayerInputPlugin))
+            .insert_state(state)
+            .add_event::<ReceivedCharacter>()
+            .init_resource::<UserSettings>()
+            .init_resource::<Score>()
+            .init_resource::<HighScores>()
+            .init_resource::<DataDir>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .add_systems(
+                Update,
+                (
+                    menu_keyboard_input.before(name_entry_input),
+                    (name_entry_input, submit_name).chain(),
+                ),
+            );
+        app
+    }
+
+    fn state(app: &App) -> GameState {
+        *app.world.resource::<State<GameState>>().get()
+    }
+
+    #[test]
+    fn escape_does_not_start_a_round() {
+        let mut app = menu_app(GameState::MainMenu);
+
+        app.world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::Escape);
+        app.update();
+        app.update();
+        assert_eq!(state(&app), GameState::MainMenu);
+
+        let mut keys = app.world.resource_mut::<ButtonInput<KeyCode>>();
+        keys.clear();
+        keys.press(KeyCode::Enter);
+        app.update();
+        app.update();
+        assert_eq!(state(&app), GameState::Playing);
+    }
+
+    #[test]
+    fn gamepad_start_submits_the_name_without_restarting() {
+        let mut app = menu_app(GameState::GameOver);
+        app.world.resource_mut::<Score>().register_kill(100);
+        app.insert_resource(NameEntry { name: "ana".into() });
+
+        app.world
+            .resource_mut::<ButtonInput<GamepadButton>>()
+            .press(GamepadButton::new(
+                Gamepad::new(0),
+                GamepadButtonType::Start,
+            ));
+        app.update();
+        app.world
+            .resource_mut::<ButtonInput<GamepadButton>>()
+            .clear();
+        // a restart would be applied now
+        app.update();
+
+        assert!(app.world.get_resource::<NameEntry>().is_none());
+        assert_eq!(
+            app.world.resource::<HighScores>().best().unwrap().name,
+            "ana"
+        );
+        assert_eq!(state(&app), GameState::GameOver);
+    }
+}
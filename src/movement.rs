@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+
+// Units per second an entity moves by
+#[derive(Component, Clone, Copy, Default, Debug, PartialEq)]
+pub struct Velocity(pub Vec2);
+
+impl Velocity {
+    // changes the velocity towards `target`, speeding up by `acceleration` and slowing
+    // down by `friction` (both in units per second squared)
+    pub fn steer(&mut self, target: Vec2, acceleration: f32, friction: f32, delta: f32) {
+        let rate = if target.length_squared() >= self.0.length_squared() {
+            acceleration
+        } else {
+            friction
+        };
+        self.0 += (target - self.0).clamp_length_max(rate * delta);
+    }
+}
+
+// Keeps `translation` inside `area`, the velocity towards a blocked side is dropped
+pub fn clamp_to(translation: &mut Vec3, velocity: &mut Velocity, area: Rect) {
+    let position = translation.truncate();
+    let clamped = position.clamp(area.min, area.max);
+
+    if clamped.x != position.x {
+        velocity.0.x = 0.;
+    }
+    if clamped.y != position.y {
+        velocity.0.y = 0.;
+    }
+    *translation = clamped.extend(translation.z);
+}
+
+// Turns `velocity` towards `target` by at most `max_angle` radians, keeping its length
+pub fn turn_towards(velocity: Vec2, target: Vec2, max_angle: f32) -> Vec2 {
+    let angle = velocity.angle_between(target);
+    if angle.is_nan() {
+        return velocity;
+    }
+    Vec2::from_angle(angle.clamp(-max_angle, max_angle)).rotate(velocity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steering_reaches_the_target_without_overshooting() {
+        let mut velocity = Velocity::default();
+        let target = Vec2::new(600., 0.);
+
+        velocity.steer(target, 3000., 3000., 0.1);
+        assert_eq!(velocity.0, Vec2::new(300., 0.));
+
+        for _ in 0..10 {
+            velocity.steer(target, 3000., 3000., 0.1);
+        }
+        assert_eq!(velocity.0, target);
+
+        velocity.steer(Vec2::ZERO, 3000., 1000., 0.1);
+        assert_eq!(velocity.0, Vec2::new(500., 0.));
+    }
+
+    #[test]
+    fn clamping_stops_at_the_edge() {
+        let mut translation = Vec3::new(120., -50., 1.);
+        let mut velocity = Velocity(Vec2::new(300., -300.));
+
+        clamp_to(
+            &mut translation,
+            &mut velocity,
+            Rect::new(-100., -100., 100., 100.),
+        );
+
+        assert_eq!(translation, Vec3::new(100., -50., 1.));
+        assert_eq!(velocity.0, Vec2::new(0., -300.));
+    }
+}
@@ -0,0 +1,234 @@
+use bevy::{audio::Volume, prelude::*, utils::HashMap};
+
+use crate::{boss::Boss, settings::UserSettings, GameState};
+
+// seconds a crossfade from one track to the next takes
+const CROSSFADE: f32 = 1.5;
+// share of the music volume left while the game is paused
+const PAUSE_DUCK: f32 = 0.3;
+// seconds it takes to duck under the pause and back
+const DUCK_FADE: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MusicTrack {
+    Menu,
+    Gameplay,
+    Boss,
+    GameOver,
+}
+
+impl MusicTrack {
+    const ALL: [MusicTrack; 4] = [
+        MusicTrack::Menu,
+        MusicTrack::Gameplay,
+        MusicTrack::Boss,
+        MusicTrack::GameOver,
+    ];
+
+    fn path(self) -> &'static str {
+        match self {
+            MusicTrack::Menu => "music/menu.wav",
+            MusicTrack::Gameplay => "music/gameplay.wav",
+            MusicTrack::Boss => "music/boss.wav",
+            MusicTrack::GameOver => "music/game_over.wav",
+        }
+    }
+
+    // track of `state`, `boss` is true while a boss is alive
+    pub fn for_state(state: GameState, boss: bool) -> Self {
+        match state {
+            GameState::MainMenu | GameState::Controls | GameState::Victory => MusicTrack::Menu,
+            GameState::Playing | GameState::Paused if boss => MusicTrack::Boss,
+            GameState::Playing | GameState::Paused => MusicTrack::Gameplay,
+            GameState::GameOver => MusicTrack::GameOver,
+        }
+    }
+}
+
+// A looping track, faded in while it's the current one and faded out otherwise
+#[derive(Component, Debug)]
+struct MusicChannel {
+    track: MusicTrack,
+    // 0 to 1, before the volume settings
+    gain: f32,
+}
+
+// Handles of every track, loaded once at startup
+#[derive(Resource)]
+struct MusicHandles(HashMap<MusicTrack, Handle<AudioSource>>);
+
+// Share of the music volume currently played, lowered while paused
+#[derive(Resource)]
+struct Ducking(f32);
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+// Loops a track for every game state and crossfades between them
+//
+// Like the sound effects, nothing is played without bevy's `AudioPlugin`.
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ducking>()
+            .add_systems(Startup, load_music.run_if(resource_exists::<GlobalVolume>))
+            .add_systems(
+                Update,
+                (start_current_track, fade_music)
+                    .chain()
+                    .run_if(resource_exists::<GlobalVolume>),
+            );
+    }
+}
+
+fn load_music(asset_server: Res<AssetServer>, mut commands: Commands) {
+    let handles = MusicTrack::ALL
+        .into_iter()
+        .map(|track| (track, asset_server.load(track.path())))
+        .collect();
+    commands.insert_resource(MusicHandles(handles));
+}
+
+// `current` moved towards `target` by at most `step`
+fn fade_towards(current: f32, target: f32, step: f32) -> f32 {
+    if current < target {
+        (current + step).min(target)
+    } else {
+        (current - step).max(target)
+    }
+}
+
+// a track fading out is faded back in instead of being started again
+fn start_current_track(
+    state: Res<State<GameState>>,
+    bosses: Query<(), With<Boss>>,
+    channels: Query<&MusicChannel>,
+    handles: Option<Res<MusicHandles>>,
+    mut commands: Commands,
+) {
+    let Some(handles) = handles else {
+        return;
+    };
+    let track = MusicTrack::for_state(*state.get(), !bosses.is_empty());
+    if channels.iter().any(|channel| channel.track == track) {
+        return;
+    }
+
+    commands.spawn((
+        AudioBundle {
+            source: handles.0[&track].clone(),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::ZERO),
+        },
+        MusicChannel { track, gain: 0. },
+    ));
+}
+
+// uses real time, the virtual clock stands still while paused
+fn fade_music(
+    mut channels: Query<(Entity, &mut MusicChannel, Option<&AudioSink>)>,
+    state: Res<State<GameState>>,
+    bosses: Query<(), With<Boss>>,
+    settings: Res<UserSettings>,
+    mut ducking: ResMut<Ducking>,
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    let delta = time.delta_seconds();
+    let track = MusicTrack::for_state(*state.get(), !bosses.is_empty());
+    let duck_target = if *state.get() == GameState::Paused {
+        PAUSE_DUCK
+    } else {
+        1.
+    };
+    ducking.0 = fade_towards(ducking.0, duck_target, delta / DUCK_FADE);
+
+    for (entity, mut channel, sink) in channels.iter_mut() {
+        let target = if channel.track == track { 1. } else { 0. };
+        channel.gain = fade_towards(channel.gain, target, delta / CROSSFADE);
+        if channel.gain == 0. && target == 0. {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if let Some(sink) = sink {
+            sink.set_volume(
+                channel.gain * ducking.0 * settings.volume.music * settings.volume.master,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    #[test]
+    fn every_state_has_a_track() {
+        assert_eq!(
+            MusicTrack::for_state(GameState::Controls, false),
+            MusicTrack::Menu
+        );
+        assert_eq!(
+            MusicTrack::for_state(GameState::Paused, false),
+            MusicTrack::Gameplay
+        );
+        assert_eq!(
+            MusicTrack::for_state(GameState::Playing, true),
+            MusicTrack::Boss
+        );
+        assert_eq!(
+            MusicTrack::for_state(GameState::GameOver, true),
+            MusicTrack::GameOver
+        );
+    }
+
+    #[test]
+    fn tracks_crossfade_and_duck_under_the_pause() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<UserSettings>()
+            .init_resource::<Ducking>()
+            .insert_resource(State::new(GameState::Paused))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                250,
+            )))
+            .add_systems(Update, fade_music);
+        app.update();
+        let menu = app
+            .world
+            .spawn(MusicChannel {
+                track: MusicTrack::Menu,
+                gain: 1.,
+            })
+            .id();
+        let gameplay = app
+            .world
+            .spawn(MusicChannel {
+                track: MusicTrack::Gameplay,
+                gain: 0.,
+            })
+            .id();
+        let gain = |app: &App, entity| app.world.get::<MusicChannel>(entity).unwrap().gain;
+
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!((gain(&app, menu) - 0.5).abs() < 1e-5);
+        assert!((gain(&app, gameplay) - 0.5).abs() < 1e-5);
+        assert_eq!(app.world.resource::<Ducking>().0, PAUSE_DUCK);
+
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world.get_entity(menu).is_none());
+        assert_eq!(gain(&app, gameplay), 1.);
+    }
+}
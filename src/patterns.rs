@@ -0,0 +1,306 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::config::ConfigError;
+
+// Bullets fired by an enemy weapon, `speed` in units per second and `cooldown` in
+// seconds between two volleys
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum BulletPattern {
+    // one shot straight to the left
+    Forward {
+        speed: f32,
+        cooldown: f32,
+    },
+    // one shot at the player
+    Aimed {
+        speed: f32,
+        cooldown: f32,
+    },
+    // `count` shots fanned out over `angle` radians, centered on the player
+    Spread {
+        count: u32,
+        angle: f32,
+        speed: f32,
+        cooldown: f32,
+    },
+    // `shots` shots at the player, `interval` seconds apart
+    AimedBurst {
+        shots: u32,
+        interval: f32,
+        speed: f32,
+        cooldown: f32,
+    },
+    // `count` shots spread evenly in every direction
+    Ring {
+        count: u32,
+        speed: f32,
+        cooldown: f32,
+    },
+    // a ring of `arms` shots turning by `spin` radians per second
+    Spiral {
+        arms: u32,
+        spin: f32,
+        speed: f32,
+        cooldown: f32,
+    },
+}
+
+impl BulletPattern {
+    fn cooldown(&self) -> f32 {
+        match *self {
+            BulletPattern::Forward { cooldown, .. }
+            | BulletPattern::Aimed { cooldown, .. }
+            | BulletPattern::Spread { cooldown, .. }
+            | BulletPattern::AimedBurst { cooldown, .. }
+            | BulletPattern::Ring { cooldown, .. }
+            | BulletPattern::Spiral { cooldown, .. } => cooldown,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: &str| {
+            Err(ConfigError::Invalid {
+                field,
+                reason: reason.into(),
+            })
+        };
+
+        let (count, speed) = match *self {
+            BulletPattern::Forward { speed, .. } | BulletPattern::Aimed { speed, .. } => (1, speed),
+            BulletPattern::Spread { count, speed, .. }
+            | BulletPattern::Ring { count, speed, .. } => (count, speed),
+            BulletPattern::AimedBurst { shots, speed, .. } => (shots, speed),
+            BulletPattern::Spiral { arms, speed, .. } => (arms, speed),
+        };
+        if count == 0 {
+            return invalid("count", "every pattern fires at least one shot");
+        }
+        if !(speed.is_finite() && speed > 0.) {
+            return invalid("speed", "bullets need a positive speed");
+        }
+        if !(self.cooldown().is_finite() && self.cooldown() > 0.) {
+            return invalid("cooldown", "must be a positive number");
+        }
+        match *self {
+            BulletPattern::AimedBurst { interval, .. }
+                if !(interval.is_finite() && interval >= 0.) =>
+            {
+                invalid("interval", "must not be negative")
+            }
+            BulletPattern::Spread { angle, .. } if !angle.is_finite() => {
+                invalid("angle", "must be a number")
+            }
+            BulletPattern::Spiral { spin, .. } if !spin.is_finite() => {
+                invalid("spin", "must be a number")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// A `BulletPattern` with its own cooldown, one per weapon
+#[derive(Debug)]
+pub struct Emitter {
+    pub pattern: BulletPattern,
+    cooldown: Timer,
+    // direction of the first spiral arm, in radians
+    angle: f32,
+    // shots of a burst still to come, and the time until the next one
+    burst_left: u32,
+    burst: Timer,
+}
+
+impl Emitter {
+    // the first volley comes after a full cooldown
+    pub fn new(pattern: BulletPattern) -> Self {
+        let interval = match pattern {
+            BulletPattern::AimedBurst { interval, .. } => interval,
+            _ => 0.,
+        };
+        Self {
+            pattern,
+            cooldown: Timer::from_seconds(pattern.cooldown(), TimerMode::Repeating),
+            angle: 0.,
+            burst_left: 0,
+            burst: Timer::from_seconds(interval, TimerMode::Repeating),
+        }
+    }
+
+    // velocities of the bullets fired after `delta`, `aim` points at the player
+    pub fn fire(&mut self, delta: Duration, aim: Vec2) -> Vec<Vec2> {
+        let mut bullets = Vec::new();
+        let cooldown_over = self.cooldown.tick(delta).just_finished();
+
+        match self.pattern {
+            BulletPattern::Forward { speed, .. } => {
+                if cooldown_over {
+                    bullets.push(Vec2::NEG_X * speed);
+                }
+            }
+            BulletPattern::Aimed { speed, .. } => {
+                if cooldown_over {
+                    bullets.push(aim * speed);
+                }
+            }
+            BulletPattern::Spread {
+                count,
+                angle,
+                speed,
+                ..
+            } => {
+                if cooldown_over {
+                    let step = if count > 1 {
+                        angle / (count - 1) as f32
+                    } else {
+                        0.
+                    };
+                    let first = -step * (count - 1) as f32 / 2.;
+                    for i in 0..count {
+                        let turn = Vec2::from_angle(first + step * i as f32);
+                        bullets.push(turn.rotate(aim) * speed);
+                    }
+                }
+            }
+            BulletPattern::AimedBurst { shots, speed, .. } => {
+                if cooldown_over {
+                    // the first shot goes right away
+                    self.burst_left = shots - 1;
+                    self.burst.reset();
+                    bullets.push(aim * speed);
+                } else if self.burst_left > 0 && self.burst.tick(delta).just_finished() {
+                    self.burst_left -= 1;
+                    bullets.push(aim * speed);
+                }
+            }
+            BulletPattern::Ring { count, speed, .. } => {
+                if cooldown_over {
+                    bullets.extend(ring(count, 0., speed));
+                }
+            }
+            BulletPattern::Spiral {
+                arms, spin, speed, ..
+            } => {
+                self.angle = (self.angle + spin * delta.as_secs_f32()) % TAU;
+                if cooldown_over {
+                    bullets.extend(ring(arms, self.angle, speed));
+                }
+            }
+        }
+
+        bullets
+    }
+}
+
+fn ring(count: u32, start: f32, speed: f32) -> impl Iterator<Item = Vec2> {
+    (0..count).map(move |i| Vec2::from_angle(start + TAU * i as f32 / count as f32) * speed)
+}
+
+// direction from `origin` to the player, straight to the left without a player
+pub fn aim(origin: Vec3, target: Option<Vec3>) -> Vec2 {
+    target
+        .and_then(|target| (target - origin).truncate().try_normalize())
+        .unwrap_or(Vec2::NEG_X)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn spread_is_centered_on_the_player() {
+        let mut emitter = Emitter::new(BulletPattern::Spread {
+            count: 3,
+            angle: 1.,
+            speed: 100.,
+            cooldown: 1.,
+        });
+
+        assert!(emitter
+            .fire(Duration::from_millis(500), Vec2::NEG_X)
+            .is_empty());
+        let bullets = emitter.fire(Duration::from_millis(500), Vec2::NEG_X);
+
+        assert_eq!(bullets.len(), 3);
+        assert!((bullets[1] - Vec2::new(-100., 0.)).length() < 1e-3);
+        assert!((bullets[0].angle_between(bullets[2]).abs() - 1.).abs() < 1e-4);
+        assert!((bullets[0].y + bullets[2].y).abs() < 1e-3);
+    }
+
+    #[test]
+    fn bursts_fire_one_shot_per_interval() {
+        let mut emitter = Emitter::new(BulletPattern::AimedBurst {
+            shots: 3,
+            interval: 0.25,
+            speed: 100.,
+            cooldown: 2.,
+        });
+
+        let fired: Vec<_> = (0..40).map(|_| emitter.fire(TICK, Vec2::Y).len()).collect();
+
+        // the cooldown ends after 2 seconds, then a shot every 0.25 seconds
+        assert_eq!(fired.iter().sum::<usize>(), 1);
+        assert_eq!(fired[39], 1);
+        let fired: Vec<_> = (0..20).map(|_| emitter.fire(TICK, Vec2::Y).len()).collect();
+        assert_eq!(fired[4], 1);
+        assert_eq!(fired.iter().sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn broken_timings_and_angles_are_rejected() {
+        let burst = |interval| BulletPattern::AimedBurst {
+            shots: 3,
+            interval,
+            speed: 100.,
+            cooldown: 1.,
+        };
+        assert!(burst(0.).validate().is_ok());
+        assert!(burst(-0.1).validate().is_err());
+        assert!(burst(f32::NAN).validate().is_err());
+
+        let spiral = BulletPattern::Spiral {
+            arms: 4,
+            spin: f32::INFINITY,
+            speed: 100.,
+            cooldown: 1.,
+        };
+        assert!(spiral.validate().is_err());
+    }
+
+    #[test]
+    fn rings_go_every_direction() {
+        let mut emitter = Emitter::new(BulletPattern::Ring {
+            count: 4,
+            speed: 10.,
+            cooldown: 0.25,
+        });
+
+        let bullets = emitter.fire(Duration::from_millis(250), Vec2::NEG_X);
+
+        assert_eq!(bullets.len(), 4);
+        assert!(bullets.iter().sum::<Vec2>().length() < 1e-3);
+        assert!(bullets
+            .iter()
+            .all(|bullet| (bullet.length() - 10.).abs() < 1e-3));
+    }
+
+    #[test]
+    fn aimed_shots_follow_the_player() {
+        let mut emitter = Emitter::new(BulletPattern::Aimed {
+            speed: 100.,
+            cooldown: 0.25,
+        });
+        let direction = aim(Vec3::new(100., 0., 0.), Some(Vec3::new(100., -50., 1.)));
+
+        assert_eq!(direction, Vec2::NEG_Y);
+        assert_eq!(
+            emitter.fire(Duration::from_millis(250), direction),
+            vec![Vec2::new(0., -100.)]
+        );
+        assert_eq!(aim(Vec3::ZERO, None), Vec2::NEG_X);
+    }
+}
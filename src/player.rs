@@ -0,0 +1,338 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision::{Collider, CollisionLayer},
+    config::GameConfig,
+    effects::HitFlash,
+    health::{health_bar, shield_bubble, DamageEvent, DeathEvent, Health, Invulnerable, Shield},
+    input::PlayerInput,
+    movement::{clamp_to, Velocity},
+    powerups::PowerUpEffects,
+    projectile::{
+        spawn_projectile, Damage, Homing, Piercing, Projectile, ShotFired, PROJECTILE_HALF_SIZE,
+    },
+    waves::WaveEnemy,
+    weapon::{Weapon, WeaponKind},
+    GameState, PlayArea, RoundEntity, RoundStart, TickSet, SHIP_SCALE, SHIP_SPRITE_SIZE,
+};
+
+// half width of the player hp bar mesh
+const HP_BAR_HALF_WIDTH: f32 = 40.;
+// how much longer a laser is than a normal shot
+const LASER_LENGTH: f32 = 4.;
+// radians per second homing missiles turn by
+const HOMING_TURN_RATE: f32 = 4.;
+
+#[derive(Component, Default)]
+pub struct Player {
+    is_shooting: bool,
+    is_bombing: bool,
+    bombs: u32,
+    // including the current one, the round is over once they run out
+    lives: u32,
+    // direction and strength from the input, never longer than 1
+    steering: Vec2,
+}
+
+impl Player {
+    pub fn bombs(&self) -> u32 {
+        self.bombs
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+}
+
+// Sent when the player's health runs out, `lives` is what's left
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LifeLost {
+    pub lives: u32,
+}
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LifeLost>()
+            .add_systems(RoundStart, spawn_player)
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_player_input.in_set(TickSet::Prepare),
+                    (use_bomb, shoot_projectile_player)
+                        .chain()
+                        .in_set(TickSet::Shoot),
+                    player_movement.in_set(TickSet::Move),
+                    lose_life.in_set(TickSet::Cleanup),
+                ),
+            );
+    }
+}
+
+fn spawn_player(
+    play_area: Res<PlayArea>,
+    config: Res<GameConfig>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let transform_player = Transform {
+        translation: start_position(&play_area),
+        scale: Vec3::splat(SHIP_SCALE),
+        ..Default::default()
+    };
+
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: asset_server.load("Ship.png"),
+                transform: transform_player,
+                ..default()
+            },
+            Player {
+                is_shooting: false,
+                is_bombing: false,
+                bombs: config.player_bombs,
+                lives: config.player_lives,
+                steering: Vec2::ZERO,
+            },
+            Velocity::default(),
+            Weapon::new(config.player_projectile_cd()),
+            Collider::circle_from_sprite(SHIP_SPRITE_SIZE, SHIP_SCALE, CollisionLayer::Player),
+            Health::new(config.player_max_hp),
+            // only charged by the shield power-up
+            Shield::new(0., 0.),
+            PowerUpEffects::default(),
+            HitFlash::new(Color::WHITE),
+            RoundEntity,
+        ))
+        .with_children(|parent| {
+            parent.spawn(health_bar(HP_BAR_HALF_WIDTH, &mut meshes, &mut materials));
+            parent.spawn(shield_bubble(
+                SHIP_SPRITE_SIZE.max_element() * 0.6,
+                &mut meshes,
+                &mut materials,
+            ));
+        });
+}
+
+// where the player spawns and respawns
+fn start_position(play_area: &PlayArea) -> Vec3 {
+    play_area.player_zone.center().extend(0.)
+}
+
+// respawns the player at the start position, the round is over once the lives run out
+fn lose_life(
+    mut deaths: EventReader<DeathEvent>,
+    mut players: Query<(&mut Player, &mut Transform, &mut Velocity, &mut Health)>,
+    play_area: Res<PlayArea>,
+    config: Res<GameConfig>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut lost: EventWriter<LifeLost>,
+) {
+    for death in deaths.read() {
+        let Ok((mut player, mut transform, mut velocity, mut health)) =
+            players.get_mut(death.entity)
+        else {
+            continue;
+        };
+        player.lives = player.lives.saturating_sub(1);
+        lost.send(LifeLost {
+            lives: player.lives,
+        });
+
+        if player.lives == 0 {
+            commands.entity(death.entity).despawn_recursive();
+            next_state.set(GameState::GameOver);
+            continue;
+        }
+
+        transform.translation = start_position(&play_area);
+        velocity.0 = Vec2::ZERO;
+        health.current = health.max;
+        commands
+            .entity(death.entity)
+            .insert(Invulnerable::new(config.respawn_invulnerability()));
+    }
+}
+
+fn apply_player_input(input: Res<PlayerInput>, mut player: Query<&mut Player>) {
+    for mut player in player.iter_mut() {
+        player.steering = input.movement();
+        player.is_shooting = input.fire;
+        if input.bomb {
+            player.is_bombing = true;
+        }
+    }
+}
+
+// steers towards the input and keeps the whole ship inside the player zone
+fn player_movement(
+    mut players: Query<(&mut Transform, &mut Velocity, &Player)>,
+    play_area: Res<PlayArea>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let zone = play_area.player_zone;
+    let radius = Vec2::splat(config.player_radius);
+    let area = Rect::from_corners(zone.min + radius, zone.max - radius);
+    let delta = time.delta_seconds();
+
+    for (mut transform, mut velocity, player) in players.iter_mut() {
+        velocity.steer(
+            player.steering * config.player_speed,
+            config.player_acceleration,
+            config.player_friction,
+            delta,
+        );
+        transform.translation += (velocity.0 * delta).extend(0.);
+        clamp_to(&mut transform.translation, &mut velocity, area);
+    }
+}
+
+// the cooldown is read from the config so it follows hot reloads
+fn shoot_projectile_player(
+    mut players: Query<(&Transform, &Player, &mut Weapon, &PowerUpEffects)>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut shots: EventWriter<ShotFired>,
+) {
+    for (transform, player, mut weapon, effects) in players.iter_mut() {
+        let cooldown = effects.cooldown(config.player_projectile_cd());
+        if weapon.cooldown() != cooldown {
+            weapon.set_cooldown(cooldown);
+        }
+        weapon.tick(time.delta());
+        if player.is_shooting {
+            weapon.trigger();
+        }
+        if !weapon.try_fire() {
+            continue;
+        }
+
+        for shot in weapon.volley() {
+            let translation = transform.translation + shot.offset.extend(0.);
+            let mut projectile = spawn_projectile(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                translation,
+                shot.direction * config.player_projectile_speed,
+                true,
+            );
+            match weapon.kind {
+                WeaponKind::Blaster | WeaponKind::Spread => (),
+                WeaponKind::Laser => {
+                    let stretch = Vec2::new(LASER_LENGTH, 1.);
+                    projectile.insert((
+                        Transform::from_translation(translation).with_scale(stretch.extend(1.)),
+                        Collider::aabb(PROJECTILE_HALF_SIZE * stretch, CollisionLayer::PlayerShot),
+                        Piercing::default(),
+                        Damage(weapon.level as f32),
+                    ));
+                }
+                WeaponKind::Homing => {
+                    projectile.insert(Homing {
+                        turn_rate: HOMING_TURN_RATE,
+                    });
+                }
+            }
+        }
+        shots.send(ShotFired { by_player: true });
+    }
+}
+
+// clears the enemy shots and hurts every enemy on screen
+fn use_bomb(
+    mut players: Query<(Entity, &mut Player)>,
+    projectiles: Query<(Entity, &Projectile)>,
+    enemies: Query<(Entity, &Transform), With<WaveEnemy>>,
+    play_area: Res<PlayArea>,
+    config: Res<GameConfig>,
+    mut damage: EventWriter<DamageEvent>,
+    mut commands: Commands,
+) {
+    for (player_entity, mut player) in players.iter_mut() {
+        if !player.is_bombing {
+            continue;
+        }
+        player.is_bombing = false;
+        if player.bombs == 0 {
+            continue;
+        }
+        player.bombs -= 1;
+
+        for (entity, projectile) in projectiles.iter() {
+            if !projectile.is_player_projectile {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        for (entity, transform) in enemies.iter() {
+            if play_area.bounds.contains(transform.translation.truncate()) {
+                damage.send(DamageEvent {
+                    target: entity,
+                    amount: config.bomb_damage,
+                    source: player_entity,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{test_app, x_of};
+
+    fn spawn_player_at(app: &mut App, x: f32, steering: Vec2) -> Entity {
+        app.world
+            .spawn((
+                Transform::from_xyz(x, 0., 0.),
+                Velocity::default(),
+                Player {
+                    is_shooting: false,
+                    is_bombing: false,
+                    bombs: 0,
+                    lives: 1,
+                    steering,
+                },
+            ))
+            .id()
+    }
+
+    #[test]
+    fn diagonal_speed_is_player_speed() {
+        let mut app = test_app(Duration::from_millis(10), player_movement);
+        let player = spawn_player_at(&mut app, -500., Vec2::ONE.normalize());
+
+        for _ in 0..50 {
+            app.update();
+        }
+
+        let speed = app.world.get::<Velocity>(player).unwrap().0.length();
+        assert!((speed - GameConfig::default().player_speed).abs() < 1e-2);
+    }
+
+    #[test]
+    fn player_stays_in_the_left_half() {
+        let mut app = test_app(Duration::from_millis(10), player_movement);
+        let player = spawn_player_at(&mut app, -100., Vec2::X);
+
+        for _ in 0..100 {
+            app.update();
+        }
+
+        let config = GameConfig::default();
+        assert_eq!(x_of(&app, player), -(config.player_radius + 10.));
+        assert_eq!(app.world.get::<Velocity>(player).unwrap().0, Vec2::ZERO);
+    }
+}
@@ -0,0 +1,293 @@
+use std::{collections::HashSet, time::Duration};
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use rand::Rng;
+
+use crate::{
+    collision::{Collider, CollisionEvent, CollisionLayer},
+    config::GameConfig,
+    enemy::Enemy,
+    health::{DeathEvent, Health, Shield},
+    movement::Velocity,
+    player::Player,
+    rng::GameRng,
+    weapon::{Weapon, WeaponKind},
+    PlayArea, RoundEntity, TickSet, FONT_PATH,
+};
+
+// radius of a power-up pickup
+const POWERUP_RADIUS: f32 = 22.;
+// rapid fire divides the cooldown by this
+const RAPID_FIRE_DIVISOR: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    Spread,
+    // fires twice as fast for a while
+    Rapid,
+    Laser,
+    Homing,
+    // absorbs damage for a while
+    Shield,
+    Heal,
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 6] = [
+        PowerUpKind::Spread,
+        PowerUpKind::Rapid,
+        PowerUpKind::Laser,
+        PowerUpKind::Homing,
+        PowerUpKind::Shield,
+        PowerUpKind::Heal,
+    ];
+
+    fn color(self) -> Color {
+        match self {
+            PowerUpKind::Spread => Color::rgb(1., 0.6, 0.2),
+            PowerUpKind::Rapid => Color::rgb(1., 0.9, 0.2),
+            PowerUpKind::Laser => Color::rgb(0.9, 0.3, 1.),
+            PowerUpKind::Homing => Color::rgb(0.3, 1., 0.5),
+            PowerUpKind::Shield => Color::rgb(0.4, 0.7, 1.),
+            PowerUpKind::Heal => Color::rgb(1., 0.3, 0.3),
+        }
+    }
+
+    // letter drawn on the pickup
+    fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::Spread => "S",
+            PowerUpKind::Rapid => "R",
+            PowerUpKind::Laser => "L",
+            PowerUpKind::Homing => "M",
+            PowerUpKind::Shield => "D",
+            PowerUpKind::Heal => "+",
+        }
+    }
+}
+
+// Pickup floating to the left, collected by touching it
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PowerUp(pub PowerUpKind);
+
+// Places a power-up on the next tick
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SpawnPowerUp {
+    pub kind: PowerUpKind,
+    pub position: Vec2,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PowerUpCollected {
+    pub kind: PowerUpKind,
+}
+
+// Time left on the temporary effects of the player
+#[derive(Component, Default, Debug)]
+pub struct PowerUpEffects {
+    pub rapid_fire: Duration,
+    pub shield: Duration,
+}
+
+impl PowerUpEffects {
+    pub fn is_active(&self) -> bool {
+        !self.rapid_fire.is_zero() || !self.shield.is_zero()
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.rapid_fire = self.rapid_fire.saturating_sub(delta);
+        self.shield = self.shield.saturating_sub(delta);
+    }
+
+    // cooldown of the player's gun, shortened during rapid fire
+    pub fn cooldown(&self, base: Duration) -> Duration {
+        if self.rapid_fire.is_zero() {
+            base
+        } else {
+            base / RAPID_FIRE_DIVISOR
+        }
+    }
+}
+
+// Drops power-ups from destroyed enemies and applies the ones the player collects
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnPowerUp>()
+            .add_event::<PowerUpCollected>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    tick_effects.in_set(TickSet::Prepare),
+                    spawn_powerups.in_set(TickSet::Spawn),
+                    powerup_movement.in_set(TickSet::Move),
+                    collect_powerups.in_set(TickSet::Resolve),
+                    drop_powerups.in_set(TickSet::Cleanup),
+                ),
+            );
+    }
+}
+
+// enemies are still around until the commands of the tick are applied
+fn drop_powerups(
+    mut deaths: EventReader<DeathEvent>,
+    enemies: Query<&Transform, With<Enemy>>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+    mut spawns: EventWriter<SpawnPowerUp>,
+) {
+    for death in deaths.read() {
+        let Ok(transform) = enemies.get(death.entity) else {
+            continue;
+        };
+        if !rng.gen_bool(config.powerup_drop_chance as f64) {
+            continue;
+        }
+
+        spawns.send(SpawnPowerUp {
+            kind: PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())],
+            position: transform.translation.truncate(),
+        });
+    }
+}
+
+fn spawn_powerups(
+    mut spawns: EventReader<SpawnPowerUp>,
+    config: Res<GameConfig>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for spawn in spawns.read() {
+        commands
+            .spawn((
+                MaterialMesh2dBundle {
+                    mesh: Mesh2dHandle(meshes.add(Circle {
+                        radius: POWERUP_RADIUS,
+                    })),
+                    material: materials.add(spawn.kind.color()),
+                    transform: Transform::from_translation(spawn.position.extend(0.5)),
+                    ..default()
+                },
+                PowerUp(spawn.kind),
+                Velocity(Vec2::new(-config.powerup_speed, 0.)),
+                Collider::circle(POWERUP_RADIUS, CollisionLayer::PowerUp),
+                RoundEntity,
+            ))
+            .with_children(|parent| {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        spawn.kind.label(),
+                        TextStyle {
+                            font: asset_server.load(FONT_PATH),
+                            font_size: 20.,
+                            color: Color::BLACK,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0., 0., 0.1),
+                    ..default()
+                });
+            });
+    }
+}
+
+fn powerup_movement(
+    mut powerups: Query<(&mut Transform, &Velocity, Entity), With<PowerUp>>,
+    play_area: Res<PlayArea>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let despawn_bounds = play_area.despawn_bounds();
+    for (mut transform, velocity, entity) in powerups.iter_mut() {
+        transform.translation += (velocity.0 * time.delta_seconds()).extend(0.);
+
+        if transform.translation.x < despawn_bounds.min.x {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn collect_powerups(
+    mut collisions: EventReader<CollisionEvent>,
+    powerups: Query<&PowerUp>,
+    mut players: Query<(&mut Weapon, &mut PowerUpEffects, &mut Health, &mut Shield), With<Player>>,
+    config: Res<GameConfig>,
+    mut commands: Commands,
+    mut collected: EventWriter<PowerUpCollected>,
+) {
+    let mut spent = HashSet::new();
+
+    for collision in collisions.read() {
+        let Some((entity_pu, entity_pl)) =
+            collision.between(CollisionLayer::PowerUp, CollisionLayer::Player)
+        else {
+            continue;
+        };
+        let (Ok(&PowerUp(kind)), Ok((mut weapon, mut effects, mut health, mut shield))) =
+            (powerups.get(entity_pu), players.get_mut(entity_pl))
+        else {
+            continue;
+        };
+        if !spent.insert(entity_pu) {
+            continue;
+        }
+
+        commands.entity(entity_pu).despawn_recursive();
+
+        match kind {
+            PowerUpKind::Spread => weapon.upgrade(WeaponKind::Spread),
+            PowerUpKind::Laser => weapon.upgrade(WeaponKind::Laser),
+            PowerUpKind::Homing => weapon.upgrade(WeaponKind::Homing),
+            PowerUpKind::Rapid => effects.rapid_fire = config.powerup_duration(),
+            PowerUpKind::Shield => {
+                effects.shield = config.powerup_duration();
+                shield.max = config.powerup_shield;
+                shield.current = config.powerup_shield;
+            }
+            PowerUpKind::Heal => {
+                health.current = (health.current + config.powerup_heal).min(health.max);
+            }
+        }
+        collected.send(PowerUpCollected { kind });
+    }
+}
+
+// the shield drops once its time is up
+fn tick_effects(mut players: Query<(&mut PowerUpEffects, &mut Shield)>, time: Res<Time>) {
+    for (mut effects, mut shield) in players.iter_mut() {
+        if !effects.is_active() {
+            continue;
+        }
+        let had_shield = !effects.shield.is_zero();
+        effects.tick(time.delta());
+        if had_shield && effects.shield.is_zero() {
+            shield.current = 0.;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rapid_fire_ends_after_its_duration() {
+        let base = Duration::from_millis(300);
+        let mut effects = PowerUpEffects {
+            rapid_fire: Duration::from_secs(1),
+            ..default()
+        };
+
+        assert_eq!(effects.cooldown(base), Duration::from_millis(150));
+        effects.tick(Duration::from_millis(750));
+        assert!(effects.is_active());
+        effects.tick(Duration::from_millis(750));
+        assert!(!effects.is_active());
+        assert_eq!(effects.cooldown(base), base);
+    }
+}
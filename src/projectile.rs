@@ -0,0 +1,216 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    collision::{Collider, CollisionLayer},
+    movement::{turn_towards, Velocity},
+    waves::WaveEnemy,
+    PlayArea, RoundEntity, TickSet,
+};
+
+// half size of the projectile rectangle
+pub const PROJECTILE_HALF_SIZE: Vec2 = Vec2::new(12., 4.);
+const PROJECTILE_COLOR: Color = Color::WHITE;
+
+#[derive(Component)]
+pub struct Projectile {
+    pub is_player_projectile: bool,
+}
+
+// Damage dealt by a hit, projectiles without it deal 1
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Damage(pub f32);
+
+// Player shot flying through enemies instead of stopping at the first, hits each once
+#[derive(Component, Default, Debug)]
+pub struct Piercing {
+    hit: Vec<Entity>,
+}
+
+impl Piercing {
+    // false when `target` was already hit by this shot
+    pub fn first_hit(&mut self, target: Entity) -> bool {
+        if self.hit.contains(&target) {
+            return false;
+        }
+        self.hit.push(target);
+        true
+    }
+}
+
+// Missile turning towards the closest enemy ahead of it
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Homing {
+    // radians per second
+    pub turn_rate: f32,
+}
+
+// Sent for every projectile fired, a volley of the player counts as one
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShotFired {
+    pub by_player: bool,
+}
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShotFired>().add_systems(
+            FixedUpdate,
+            (steer_homing_projectiles, projectile_movement)
+                .chain()
+                .in_set(TickSet::Move),
+        );
+    }
+}
+
+// projectile flying with `velocity`, turned to point where it's going
+pub fn spawn_projectile<'a>(
+    commands: &'a mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    translation: Vec3,
+    velocity: Vec2,
+    is_player_projectile: bool,
+) -> EntityCommands<'a> {
+    let shape = Mesh2dHandle(meshes.add(Rectangle {
+        half_size: PROJECTILE_HALF_SIZE,
+    }));
+
+    let layer = if is_player_projectile {
+        CollisionLayer::PlayerShot
+    } else {
+        CollisionLayer::EnemyShot
+    };
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: shape,
+            material: materials.add(PROJECTILE_COLOR),
+            transform: Transform::from_translation(translation)
+                .with_rotation(Quat::from_rotation_z(velocity.y.atan2(velocity.x))),
+            ..default()
+        },
+        Projectile {
+            is_player_projectile,
+        },
+        Velocity(velocity),
+        Collider::aabb(PROJECTILE_HALF_SIZE, layer),
+        RoundEntity,
+    ))
+}
+
+// only enemies in front of a missile are chased, the ones it has passed are left alone
+fn steer_homing_projectiles(
+    mut missiles: Query<(&mut Transform, &mut Velocity, &Homing)>,
+    enemies: Query<&Transform, (With<WaveEnemy>, Without<Homing>)>,
+    time: Res<Time>,
+) {
+    for (mut transform, mut velocity, homing) in missiles.iter_mut() {
+        let position = transform.translation.truncate();
+        let closest = enemies
+            .iter()
+            .map(|enemy| enemy.translation.truncate())
+            .filter(|enemy| enemy.x > position.x)
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            });
+        let Some(target) = closest else {
+            continue;
+        };
+
+        velocity.0 = turn_towards(
+            velocity.0,
+            target - position,
+            homing.turn_rate * time.delta_seconds(),
+        );
+        transform.rotation = Quat::from_rotation_z(velocity.0.y.atan2(velocity.0.x));
+    }
+}
+
+fn projectile_movement(
+    mut projectiles: Query<(&mut Transform, &Velocity, Entity), With<Projectile>>,
+    play_area: Res<PlayArea>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let despawn_bounds = play_area.despawn_bounds();
+    for (mut transform, velocity, entity) in projectiles.iter_mut() {
+        transform.translation += (velocity.0 * time.delta_seconds()).extend(0.);
+
+        if !despawn_bounds.contains(transform.translation.truncate()) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{test_app, x_of};
+
+    fn spawn_projectile_at(app: &mut App, x: f32, velocity: Vec2) -> Entity {
+        app.world
+            .spawn((
+                Transform::from_xyz(x, 0., 0.),
+                Projectile {
+                    is_player_projectile: velocity.x > 0.,
+                },
+                Velocity(velocity),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn projectiles_move_at_their_speeds() {
+        let mut app = test_app(Duration::from_millis(100), projectile_movement);
+        let player_projectile = spawn_projectile_at(&mut app, 0., Vec2::new(600., 0.));
+        let rocket_projectile = spawn_projectile_at(&mut app, 0., Vec2::new(-300., 0.));
+
+        app.update();
+
+        assert!((x_of(&app, player_projectile) - 60.).abs() < 1e-3);
+        assert!((x_of(&app, rocket_projectile) + 30.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn projectiles_despawn_past_the_despawn_margin() {
+        let mut app = test_app(Duration::from_millis(100), projectile_movement);
+        let despawn_bounds = PlayArea::default().despawn_bounds();
+        let leaving = spawn_projectile_at(&mut app, despawn_bounds.max.x - 1., Vec2::new(600., 0.));
+        let staying =
+            spawn_projectile_at(&mut app, despawn_bounds.max.x - 1., Vec2::new(-300., 0.));
+
+        app.update();
+
+        assert!(app.world.get_entity(leaving).is_none());
+        assert!(app.world.get_entity(staying).is_some());
+    }
+
+    #[test]
+    fn missiles_turn_towards_the_closest_enemy_ahead() {
+        let mut app = test_app(Duration::from_millis(100), steer_homing_projectiles);
+        let missile = spawn_projectile_at(&mut app, 0., Vec2::new(600., 0.));
+        app.world
+            .entity_mut(missile)
+            .insert(Homing { turn_rate: 1. });
+        app.world
+            .spawn((Transform::from_xyz(500., 300., 0.), WaveEnemy));
+        // closer, but already passed
+        app.world
+            .spawn((Transform::from_xyz(-100., -100., 0.), WaveEnemy));
+
+        app.update();
+        let velocity = app.world.get::<Velocity>(missile).unwrap().0;
+
+        assert!(velocity.y > 0.);
+        assert!((velocity.length() - 600.).abs() < 1e-2);
+        assert!((velocity.angle_between(Vec2::X) + 0.1).abs() < 1e-4);
+    }
+}
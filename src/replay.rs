@@ -0,0 +1,254 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    input::{sample_input, InputSampling, PlayerInput},
+    rng::GameRng,
+};
+
+const MAGIC: &[u8; 4] = b"RIRP";
+// bumped whenever the input or the simulation changes in a way old replays can't follow
+const VERSION: u8 = 3;
+// run length followed by the bytes of the input
+const RUN_LEN: usize = 4;
+// magic, version and seed
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("could not read file: {0}")]
+    Io(#[from] io::Error),
+    #[error("not a replay file")]
+    NotAReplay,
+    #[error("replay was recorded with version {0}, this build plays version {VERSION}")]
+    UnsupportedVersion(u8),
+    #[error("replay file is truncated")]
+    Truncated,
+}
+
+// Seed of a round and the input of every tick it lasted
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    pub seed: u64,
+    pub inputs: Vec<PlayerInput>,
+}
+
+impl Recording {
+    // header followed by runs of equal inputs, input rarely changes from tick to tick
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+
+        let mut inputs = self.inputs.iter().peekable();
+        while let Some(input) = inputs.next() {
+            let mut run = 1;
+            while run < u8::MAX && inputs.next_if_eq(&input).is_some() {
+                run += 1;
+            }
+            bytes.push(run);
+            bytes.extend_from_slice(&input.to_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(ReplayError::NotAReplay);
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(ReplayError::Truncated);
+        }
+        let version = bytes[MAGIC.len()];
+        if version != VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let mut seed = [0; 8];
+        seed.copy_from_slice(&bytes[MAGIC.len() + 1..HEADER_LEN]);
+
+        let runs = &bytes[HEADER_LEN..];
+        if runs.len() % RUN_LEN != 0 {
+            return Err(ReplayError::Truncated);
+        }
+        let inputs = runs
+            .chunks_exact(RUN_LEN)
+            .flat_map(|run| {
+                let input = PlayerInput::from_bytes([run[1], run[2], run[3]]);
+                std::iter::repeat(input).take(run[0] as usize)
+            })
+            .collect();
+
+        Ok(Self {
+            seed: u64::from_le_bytes(seed),
+            inputs,
+        })
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, ReplayError> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.encode())
+    }
+}
+
+// Records the rounds played to `path`, every round overwrites the previous one
+#[derive(Resource)]
+pub struct Recorder {
+    path: PathBuf,
+    recording: Recording,
+}
+
+// Feeds the input of a recording instead of the keyboard
+#[derive(Resource)]
+pub struct Playback {
+    recording: Recording,
+    tick: usize,
+}
+
+impl Playback {
+    pub fn ticks(&self) -> usize {
+        self.tick
+    }
+
+    // every recorded input has been played
+    pub fn ran_out(&self) -> bool {
+        self.tick >= self.recording.inputs.len()
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    Record(PathBuf),
+    Play(Recording),
+}
+
+pub struct ReplayPlugin {
+    pub mode: ReplayMode,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.mode {
+            ReplayMode::Off => (),
+            ReplayMode::Record(path) => {
+                app.insert_resource(Recorder {
+                    path: path.clone(),
+                    recording: Recording::default(),
+                })
+                .add_systems(FixedUpdate, record_input.after(InputSampling));
+            }
+            ReplayMode::Play(recording) => {
+                app.insert_resource(Playback {
+                    recording: recording.clone(),
+                    tick: 0,
+                })
+                .add_systems(
+                    FixedUpdate,
+                    play_back_input.in_set(InputSampling).after(sample_input),
+                );
+            }
+        }
+    }
+}
+
+// run when a round starts, after the rng got its seed
+pub fn start_recording(rng: Res<GameRng>, recorder: Option<ResMut<Recorder>>) {
+    if let Some(mut recorder) = recorder {
+        recorder.recording = Recording {
+            seed: rng.seed(),
+            inputs: Vec::new(),
+        };
+    }
+}
+
+// run when a round ends
+pub fn save_recording(recorder: Option<Res<Recorder>>) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    match recorder.recording.save_to(&recorder.path) {
+        Ok(()) => info!(
+            "recorded {} ticks to {}",
+            recorder.recording.inputs.len(),
+            recorder.path.display()
+        ),
+        Err(err) => warn!(
+            "could not save replay to {}: {}",
+            recorder.path.display(),
+            err
+        ),
+    }
+}
+
+fn record_input(input: Res<PlayerInput>, mut recorder: ResMut<Recorder>) {
+    recorder.recording.inputs.push(*input);
+}
+
+// the app quits once the input runs out, see `finish_replay`
+fn play_back_input(mut playback: ResMut<Playback>, mut input: ResMut<PlayerInput>) {
+    let tick = playback.tick;
+    *input = playback
+        .recording
+        .inputs
+        .get(tick)
+        .copied()
+        .unwrap_or_default();
+    playback.tick += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording_round_trips_through_bytes() {
+        let fire = PlayerInput {
+            fire: true,
+            ..default()
+        };
+        let up = PlayerInput::moving(Vec2::Y);
+        let mut inputs = vec![PlayerInput::default(); 300];
+        inputs.push(fire);
+        inputs.extend([up; 20]);
+        let recording = Recording { seed: 42, inputs };
+
+        let bytes = recording.encode();
+
+        // 300 idle ticks take two runs
+        assert_eq!(bytes.len(), HEADER_LEN + 4 * RUN_LEN);
+        assert_eq!(Recording::decode(&bytes).unwrap(), recording);
+    }
+
+    #[test]
+    fn broken_files_are_rejected() {
+        let mut bytes = Recording::default().encode();
+
+        assert!(matches!(
+            Recording::decode(b"hello"),
+            Err(ReplayError::NotAReplay)
+        ));
+        assert!(matches!(
+            Recording::decode(&bytes[..6]),
+            Err(ReplayError::Truncated)
+        ));
+        bytes[MAGIC.len()] = VERSION + 1;
+        assert!(matches!(
+            Recording::decode(&bytes),
+            Err(ReplayError::UnsupportedVersion(_))
+        ));
+    }
+}
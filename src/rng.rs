@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use rand::{Error, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::config::GameConfig;
+
+// Source of all gameplay randomness
+//
+// Re-seeded at the start of every round, so a seed and the same inputs always
+// play out the same game.
+#[derive(Resource, Debug)]
+pub struct GameRng {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+// Seed passed with `--seed` or read from a replay, takes priority over the `seed` of the config
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct FixedSeed(Option<u64>);
+
+pub struct RngPlugin {
+    pub seed: Option<u64>,
+}
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        match self.seed {
+            Some(seed) => info!("every round uses seed {}", seed),
+            None => info!("no seed given, every round picks its own"),
+        }
+
+        app.insert_resource(FixedSeed(self.seed))
+            .insert_resource(GameRng::new(self.seed.unwrap_or_default()));
+    }
+}
+
+// run when a round starts, before anything random happens in it
+pub fn reseed_rng(fixed: Res<FixedSeed>, config: Res<GameConfig>, mut rng: ResMut<GameRng>) {
+    let seed = fixed.0.or(config.seed).unwrap_or_else(rand::random);
+    info!("round seed: {} (replay it with --seed {})", seed, seed);
+    *rng = GameRng::new(seed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn same_seed_gives_the_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = GameRng::new(seed);
+            (0..16).map(|_| rng.gen()).collect::<Vec<u32>>()
+        };
+
+        assert_eq!(numbers(42), numbers(42));
+        assert_ne!(numbers(42), numbers(7));
+    }
+}
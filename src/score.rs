@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::health::{DeathEvent, HealthUpdate};
+
+// time without kills after which the combo multiplier drops by one step
+const COMBO_WINDOW: Duration = Duration::from_millis(2500);
+// highest combo multiplier
+const MAX_MULTIPLIER: u32 = 8;
+
+// Points awarded for destroying an enemy, before the combo multiplier
+#[derive(Component, Clone, Copy)]
+pub struct ScoreValue(pub u32);
+
+#[derive(Resource, Debug)]
+pub struct Score {
+    pub kills: u32,
+    pub points: u32,
+    // applied to the next kill, grows with every kill inside the combo window
+    pub multiplier: u32,
+    combo_timer: Timer,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            kills: 0,
+            points: 0,
+            multiplier: 1,
+            combo_timer: Timer::new(COMBO_WINDOW, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Score {
+    pub fn register_kill(&mut self, value: u32) {
+        self.kills += 1;
+        self.points += value * self.multiplier;
+        self.multiplier = (self.multiplier + 1).min(MAX_MULTIPLIER);
+        self.combo_timer.reset();
+    }
+
+    // returns true when the multiplier decayed
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        if self.multiplier == 1 {
+            return false;
+        }
+
+        if self.combo_timer.tick(delta).just_finished() {
+            self.multiplier -= 1;
+            return true;
+        }
+
+        false
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScoreUpdate;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .configure_sets(FixedUpdate, ScoreUpdate.after(HealthUpdate))
+            .add_systems(
+                FixedUpdate,
+                (decay_combo, award_kills).chain().in_set(ScoreUpdate),
+            );
+    }
+}
+
+fn award_kills(
+    mut deaths: EventReader<DeathEvent>,
+    values: Query<&ScoreValue>,
+    mut score: ResMut<Score>,
+) {
+    for death in deaths.read() {
+        if let Ok(value) = values.get(death.entity) {
+            score.register_kill(value.0);
+        }
+    }
+}
+
+fn decay_combo(time: Res<Time>, mut score: ResMut<Score>) {
+    // ticking the timer alone is not a change the HUD cares about
+    if score.bypass_change_detection().tick(time.delta()) {
+        score.set_changed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combo_multiplies_points() {
+        let mut score = Score::default();
+
+        score.register_kill(100);
+        score.register_kill(100);
+        score.register_kill(100);
+
+        assert_eq!(score.kills, 3);
+        assert_eq!(score.points, 100 + 200 + 300);
+        assert_eq!(score.multiplier, 4);
+    }
+
+    #[test]
+    fn combo_decays_one_step_per_window() {
+        let mut score = Score::default();
+        score.register_kill(100);
+        score.register_kill(100);
+
+        assert!(!score.tick(COMBO_WINDOW / 2));
+        assert_eq!(score.multiplier, 3);
+        assert!(score.tick(COMBO_WINDOW / 2));
+        assert_eq!(score.multiplier, 2);
+        assert!(score.tick(COMBO_WINDOW));
+        assert_eq!(score.multiplier, 1);
+        assert!(!score.tick(COMBO_WINDOW * 10));
+        assert_eq!(score.multiplier, 1);
+    }
+}
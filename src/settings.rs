@@ -0,0 +1,166 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::Bindings,
+    storage::{load_ron, save_ron, DataDir},
+};
+
+const FILE_NAME: &str = "settings.ron";
+// how much a click on a volume in the menu raises it
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VolumeChannel {
+    // scales every other channel
+    Master,
+    // sound effects
+    Sfx,
+    Music,
+}
+
+impl VolumeChannel {
+    pub const ALL: [VolumeChannel; 3] = [
+        VolumeChannel::Master,
+        VolumeChannel::Sfx,
+        VolumeChannel::Music,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VolumeChannel::Master => "master volume",
+            VolumeChannel::Sfx => "effects volume",
+            VolumeChannel::Music => "music volume",
+        }
+    }
+}
+
+// Loudness of every channel, from 0 (muted) to 1
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct Volumes {
+    pub master: f32,
+    pub sfx: f32,
+    pub music: f32,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Self {
+            master: 1.,
+            sfx: 0.8,
+            music: 0.6,
+        }
+    }
+}
+
+impl Volumes {
+    pub fn get(&self, channel: VolumeChannel) -> f32 {
+        match channel {
+            VolumeChannel::Master => self.master,
+            VolumeChannel::Sfx => self.sfx,
+            VolumeChannel::Music => self.music,
+        }
+    }
+
+    // raised by one step, full volume goes back to muted
+    pub fn step(&mut self, channel: VolumeChannel) {
+        let volume = match channel {
+            VolumeChannel::Master => &mut self.master,
+            VolumeChannel::Sfx => &mut self.sfx,
+            VolumeChannel::Music => &mut self.music,
+        };
+        let steps = (*volume / VOLUME_STEP).round() + 1.;
+        *volume = if steps * VOLUME_STEP > 1. + f32::EPSILON {
+            0.
+        } else {
+            (steps * VOLUME_STEP).min(1.)
+        };
+    }
+}
+
+// Preferences of the player, kept in the data folder next to the high scores
+//
+// Unlike `config::GameConfig` these are changed from the menus and saved right away.
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct UserSettings {
+    pub controls: Bindings,
+    pub volume: Volumes,
+}
+
+impl UserSettings {
+    pub fn path(data_dir: &DataDir) -> Option<PathBuf> {
+        data_dir.file(FILE_NAME)
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        load_ron(path, "settings")
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        save_ron(self, path)
+    }
+
+    pub fn save(&self, data_dir: &DataDir) {
+        let Some(path) = Self::path(data_dir) else {
+            warn!("no data directory, settings are not saved");
+            return;
+        };
+        if let Err(err) = self.save_to(&path) {
+            warn!("could not save settings to {}: {}", path.display(), err);
+        }
+    }
+}
+
+// Loads the settings from the `DataDir` inserted before it
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = UserSettings::path(app.world.resource::<DataDir>())
+            .map(|path| UserSettings::load_from(&path))
+            .unwrap_or_default();
+        app.insert_resource(settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+    use crate::input::InputAction;
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let dir = env::temp_dir().join(format!("rusty-invaders-settings-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join(FILE_NAME);
+        let mut settings = UserSettings::default();
+        settings.controls.bind_key(InputAction::Bomb, KeyCode::KeyQ);
+        settings.volume.music = 0.3;
+
+        settings.save_to(&path).unwrap();
+
+        assert_eq!(UserSettings::load_from(&path), settings);
+    }
+
+    #[test]
+    fn volume_steps_wrap_around_to_muted() {
+        let mut volume = Volumes::default();
+
+        volume.step(VolumeChannel::Sfx);
+        assert!((volume.sfx - 0.9).abs() < 1e-6);
+        volume.step(VolumeChannel::Sfx);
+        assert_eq!(volume.sfx, 1.);
+        volume.step(VolumeChannel::Sfx);
+        assert_eq!(volume.sfx, 0.);
+        assert_eq!(volume.master, Volumes::default().master);
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+// overrides the folder the game keeps player data in
+pub const DATA_DIR_ENV: &str = "RUSTY_INVADERS_DATA_DIR";
+
+// Folder high scores and settings are kept in, without one nothing is saved
+#[derive(Resource, Clone, Default, Debug)]
+pub struct DataDir(pub Option<PathBuf>);
+
+impl DataDir {
+    pub fn file(&self, name: &str) -> Option<PathBuf> {
+        Some(self.0.as_ref()?.join(name))
+    }
+}
+
+// platform data dir (e.g. `~/.local/share/rusty-invaders`) unless overridden by the env var
+pub fn data_dir() -> Option<PathBuf> {
+    match env::var_os(DATA_DIR_ENV) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => Some(dirs::data_dir()?.join("rusty-invaders")),
+    }
+}
+
+// a missing file gives the default, a corrupt one is moved aside so it isn't overwritten
+pub fn load_ron<T: DeserializeOwned + Default>(path: &Path, what: &str) -> T {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return T::default(),
+        Err(err) => {
+            warn!("could not read {} from {}: {}", what, path.display(), err);
+            return T::default();
+        }
+    };
+
+    match ron::from_str(&text) {
+        Ok(value) => value,
+        Err(err) => {
+            let backup = path.with_extension("ron.corrupt");
+            warn!(
+                "{} in {} are corrupt ({}), moving them to {}",
+                what,
+                path.display(),
+                err,
+                backup.display()
+            );
+            if let Err(err) = fs::rename(path, &backup) {
+                warn!("could not move corrupt {}: {}", what, err);
+            }
+            T::default()
+        }
+    }
+}
+
+pub fn save_ron<T: Serialize>(value: &T, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(path, text)
+}
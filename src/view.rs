@@ -0,0 +1,144 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{ScalingMode, Viewport},
+        view::RenderLayers,
+    },
+    window::{PrimaryWindow, WindowMode},
+};
+
+use crate::PlayArea;
+
+// Camera showing exactly the bounds of the `PlayArea`
+//
+// Its viewport is the biggest rect of the same aspect ratio that fits the window, the
+// rest of the window is left black.
+#[derive(Component)]
+pub struct GameCamera;
+
+// Shows the `PlayArea` at any window size and switches fullscreen with F11
+//
+// UI is laid out as if the screen was the size of the `PlayArea` bounds and scaled
+// with the game.
+pub struct ViewPlugin;
+
+impl Plugin for ViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_cameras)
+            .add_systems(Update, (toggle_fullscreen, fit_to_window).chain());
+    }
+}
+
+fn spawn_cameras(mut commands: Commands, play_area: Res<PlayArea>) {
+    // only clears the bars around the game camera
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: -1,
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::none(),
+    ));
+
+    let mut camera = Camera2dBundle {
+        camera: Camera {
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        ..default()
+    };
+    let size = play_area.bounds.size();
+    camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: size.x,
+        height: size.y,
+    };
+    let center = play_area.bounds.center();
+    camera.transform.translation.x = center.x;
+    camera.transform.translation.y = center.y;
+    commands.spawn((camera, GameCamera, IsDefaultUiCamera));
+}
+
+// biggest rect with the aspect ratio of `virtual_size` centered in `window_size`
+fn letterbox(window_size: UVec2, virtual_size: Vec2) -> (UVec2, UVec2) {
+    let window = window_size.as_vec2();
+    let scale = (window / virtual_size).min_element();
+    let size = (virtual_size * scale).round().as_uvec2().min(window_size);
+    (window_size.saturating_sub(size) / 2, size)
+}
+
+fn fit_to_window(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<&mut Camera, With<GameCamera>>,
+    play_area: Res<PlayArea>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    // minimized
+    if window_size.cmpeq(UVec2::ZERO).any() {
+        return;
+    }
+
+    let virtual_size = play_area.bounds.size();
+    let (position, size) = letterbox(window_size, virtual_size);
+    for mut camera in cameras.iter_mut() {
+        let unchanged = camera.viewport.as_ref().is_some_and(|viewport| {
+            viewport.physical_position == position && viewport.physical_size == size
+        });
+        if !unchanged {
+            camera.viewport = Some(Viewport {
+                physical_position: position,
+                physical_size: size,
+                ..default()
+            });
+        }
+    }
+
+    let scale = size.y as f32 / (window.scale_factor() * virtual_size.y);
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+fn toggle_fullscreen(keys: Res<ButtonInput<KeyCode>>, mut windows: Query<&mut Window>) {
+    if !keys.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    for mut window in windows.iter_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_keeps_the_aspect_ratio() {
+        let virtual_size = Vec2::new(1920., 1080.);
+
+        // too wide, bars left and right
+        assert_eq!(
+            letterbox(UVec2::new(2560, 1080), virtual_size),
+            (UVec2::new(320, 0), UVec2::new(1920, 1080))
+        );
+        // too tall, bars at the top and bottom
+        assert_eq!(
+            letterbox(UVec2::new(1280, 1024), virtual_size),
+            (UVec2::new(0, 152), UVec2::new(1280, 720))
+        );
+        assert_eq!(
+            letterbox(UVec2::new(3840, 2160), virtual_size),
+            (UVec2::ZERO, UVec2::new(3840, 2160))
+        );
+    }
+}
@@ -0,0 +1,584 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{asset::LoadState, prelude::*};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    boss::BossDefinition,
+    config::{ConfigError, RonAsset, RonAssetLoader},
+    rng::GameRng,
+};
+
+// path of the wave definitions, relative to the assets folder
+const WAVES_PATH: &str = "waves.ron";
+// horizontal distance between ships of a `Vee` or `Column` formation
+const FORMATION_SPACING: f32 = 120.;
+
+// Kind of enemy, its stats and behavior are defined in `assets/enemies.ron`
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EnemyKind {
+    Rocket,
+    Sine,
+    Kamikaze,
+    Turret,
+    Shielded,
+}
+
+// Where the ships of a group appear, `y` is a fraction of the half play field height
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Formation {
+    // every ship at its own random height
+    Random,
+    // evenly spread over the height of the screen
+    Line,
+    // one after another at a single random height
+    Column,
+    // V shape pointing at the player
+    Vee,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnGroup {
+    pub enemy: EnemyKind,
+    pub count: u32,
+    pub formation: Formation,
+    // seconds between two ships of the group
+    pub spawn_interval: f32,
+    // seconds after the start of the wave before the first ship
+    #[serde(default)]
+    pub delay: f32,
+    // chance of a ship being able to shoot
+    #[serde(default)]
+    pub shooting_chance: f32,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WaveDefinition {
+    pub groups: Vec<SpawnGroup>,
+}
+
+// Multipliers applied once per completed loop through the wave list
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Escalation {
+    pub count: f32,
+    pub hp: f32,
+    pub speed: f32,
+    pub spawn_interval: f32,
+    // added to the shooting chance
+    pub shooting_chance: f32,
+}
+
+impl Default for Escalation {
+    fn default() -> Self {
+        Self {
+            count: 1.25,
+            hp: 1.25,
+            speed: 1.1,
+            spawn_interval: 0.85,
+            shooting_chance: 0.1,
+        }
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WaveList {
+    // seconds between the screen being cleared and the next wave
+    pub delay_between_waves: f32,
+    pub waves: Vec<WaveDefinition>,
+    #[serde(default)]
+    pub escalation: Escalation,
+    // fought after the last wave instead of starting the list over
+    #[serde(default)]
+    pub boss: Option<BossDefinition>,
+}
+
+impl Default for WaveList {
+    // endless trickle of rockets, used until `assets/waves.ron` is loaded
+    fn default() -> Self {
+        Self {
+            delay_between_waves: 2.,
+            waves: vec![WaveDefinition {
+                groups: vec![SpawnGroup {
+                    enemy: EnemyKind::Rocket,
+                    count: 10,
+                    formation: Formation::Random,
+                    spawn_interval: 2.,
+                    delay: 0.,
+                    shooting_chance: 0.2,
+                }],
+            }],
+            escalation: Escalation::default(),
+            boss: None,
+        }
+    }
+}
+
+impl RonAsset for WaveList {
+    const EXTENSION: &'static str = "waves.ron";
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: &str| {
+            Err(ConfigError::Invalid {
+                field,
+                reason: reason.into(),
+            })
+        };
+
+        if !(self.delay_between_waves.is_finite() && self.delay_between_waves >= 0.) {
+            return invalid("delay_between_waves", "must not be negative");
+        }
+        if self.waves.is_empty() {
+            return invalid("waves", "needs at least one wave");
+        }
+        for wave in &self.waves {
+            if wave.groups.is_empty() {
+                return invalid("groups", "every wave needs at least one group");
+            }
+            for group in &wave.groups {
+                if group.count == 0 {
+                    return invalid("count", "must be at least 1");
+                }
+                if !(group.spawn_interval >= 0. && group.delay >= 0.) {
+                    return invalid("spawn_interval", "timings must not be negative");
+                }
+                if !(0. ..=1.).contains(&group.shooting_chance) {
+                    return invalid("shooting_chance", "must be between 0 and 1");
+                }
+            }
+        }
+        let escalation = &self.escalation;
+        for (field, factor) in [
+            ("count", escalation.count),
+            ("hp", escalation.hp),
+            ("speed", escalation.speed),
+            ("spawn_interval", escalation.spawn_interval),
+        ] {
+            if !(factor.is_finite() && factor > 0.) {
+                return invalid(field, "escalation factors must be positive");
+            }
+        }
+        if !(escalation.shooting_chance.is_finite() && escalation.shooting_chance >= 0.) {
+            return invalid("shooting_chance", "escalation must not be negative");
+        }
+        if let Some(boss) = &self.boss {
+            boss.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpawnOrder {
+    pub enemy: EnemyKind,
+    // `x` is the distance behind the right edge, `y` a fraction of the half height
+    pub offset: Vec2,
+    pub hp_multiplier: f32,
+    pub speed_multiplier: f32,
+    pub is_shooting: bool,
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct WaveStarted {
+    pub number: u32,
+    // the boss appears instead of a wave of enemies, see `boss::BossPlugin`
+    pub boss: bool,
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct SpawnEnemy(pub SpawnOrder);
+
+#[derive(Debug)]
+enum Phase {
+    // waiting before the next wave
+    Delay(Timer),
+    // spawn orders of the current wave sorted by their time
+    Spawning {
+        elapsed: Duration,
+        queue: VecDeque<(f32, SpawnOrder)>,
+    },
+    // everything is spawned, waiting for the screen to clear
+    Clearing,
+    // the boss is beaten, no more waves come
+    Finished,
+}
+
+// Enemies counted as alive by the director, a wave ends once none are left
+#[derive(Component)]
+pub struct WaveEnemy;
+
+#[derive(Resource, Debug)]
+pub struct WaveDirector {
+    list: WaveList,
+    // number of the current wave, starting at 1
+    wave: u32,
+    phase: Phase,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self::new(WaveList::default())
+    }
+}
+
+impl WaveDirector {
+    pub fn new(list: WaveList) -> Self {
+        let phase = Phase::Delay(Self::delay(&list));
+        Self {
+            list,
+            wave: 0,
+            phase,
+        }
+    }
+
+    fn delay(list: &WaveList) -> Timer {
+        Timer::from_seconds(list.delay_between_waves, TimerMode::Once)
+    }
+
+    pub fn boss(&self) -> Option<&BossDefinition> {
+        self.list.boss.as_ref()
+    }
+
+    // the wave after the last one of the list, when the list has a boss
+    fn is_boss_wave(&self) -> bool {
+        self.list.boss.is_some() && self.wave as usize == self.list.waves.len() + 1
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.list.clone());
+    }
+
+    // new definitions take effect from the next wave on
+    pub fn set_list(&mut self, list: WaveList) {
+        self.list = list;
+    }
+
+    pub fn tick(
+        &mut self,
+        delta: Duration,
+        alive_enemies: usize,
+        rng: &mut impl Rng,
+        started: &mut impl FnMut(WaveStarted),
+        spawn: &mut impl FnMut(SpawnOrder),
+    ) {
+        match &mut self.phase {
+            Phase::Delay(timer) => {
+                if timer.tick(delta).finished() {
+                    self.wave += 1;
+                    if self.is_boss_wave() {
+                        started(WaveStarted {
+                            number: self.wave,
+                            boss: true,
+                        });
+                        self.phase = Phase::Clearing;
+                        return;
+                    }
+                    started(WaveStarted {
+                        number: self.wave,
+                        boss: false,
+                    });
+                    self.phase = Phase::Spawning {
+                        elapsed: Duration::ZERO,
+                        queue: self.build_wave(rng),
+                    };
+                    // the first ships of the wave appear right away
+                    self.tick(Duration::ZERO, alive_enemies, rng, started, spawn);
+                }
+            }
+            Phase::Spawning { elapsed, queue } => {
+                if queue.is_empty() {
+                    self.phase = Phase::Clearing;
+                    return;
+                }
+
+                *elapsed += delta;
+                let elapsed = elapsed.as_secs_f32();
+                while queue.front().is_some_and(|(time, _)| *time <= elapsed) {
+                    if let Some((_, order)) = queue.pop_front() {
+                        spawn(order);
+                    }
+                }
+            }
+            Phase::Clearing => {
+                if alive_enemies == 0 {
+                    self.phase = if self.is_boss_wave() {
+                        Phase::Finished
+                    } else {
+                        Phase::Delay(Self::delay(&self.list))
+                    };
+                }
+            }
+            Phase::Finished => (),
+        }
+    }
+
+    fn build_wave(&self, rng: &mut impl Rng) -> VecDeque<(f32, SpawnOrder)> {
+        let index = (self.wave - 1) as usize % self.list.waves.len();
+        let escalation = &self.list.escalation;
+        let level = ((self.wave - 1) as usize / self.list.waves.len()) as i32;
+
+        let mut orders = Vec::new();
+
+        for group in &self.list.waves[index].groups {
+            let count = (group.count as f32 * escalation.count.powi(level)).round() as u32;
+            let interval = group.spawn_interval * escalation.spawn_interval.powi(level);
+            let shooting_chance =
+                (group.shooting_chance + escalation.shooting_chance * level as f32).clamp(0., 1.);
+            let column_y = rng.gen_range(-0.75..0.75);
+
+            for i in 0..count {
+                let offset = match group.formation {
+                    Formation::Random => Vec2::new(0., rng.gen_range(-0.75..0.75)),
+                    Formation::Line => {
+                        let t = if count > 1 {
+                            i as f32 / (count - 1) as f32
+                        } else {
+                            0.5
+                        };
+                        Vec2::new(0., -0.75 + 1.5 * t)
+                    }
+                    Formation::Column => Vec2::new(0., column_y),
+                    Formation::Vee => {
+                        let row = i.div_ceil(2) as f32;
+                        let side = if i % 2 == 0 { 1. } else { -1. };
+                        Vec2::new(row * FORMATION_SPACING, side * row * 0.15)
+                    }
+                };
+
+                orders.push((
+                    group.delay + i as f32 * interval,
+                    SpawnOrder {
+                        enemy: group.enemy,
+                        offset,
+                        hp_multiplier: escalation.hp.powi(level),
+                        speed_multiplier: escalation.speed.powi(level),
+                        is_shooting: rng.gen_bool(shooting_chance as f64),
+                    },
+                ));
+            }
+        }
+
+        // stable, so ships sharing a time keep the order of the file
+        orders.sort_by(|a, b| a.0.total_cmp(&b.0));
+        orders.into()
+    }
+}
+
+#[derive(Resource)]
+pub struct WaveListHandle(Handle<WaveList>);
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WaveSpawning;
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WaveList>()
+            .init_asset_loader::<RonAssetLoader<WaveList>>()
+            .init_resource::<WaveDirector>()
+            .add_event::<WaveStarted>()
+            .add_event::<SpawnEnemy>()
+            .add_systems(Startup, load_waves)
+            .add_systems(Update, apply_waves)
+            .add_systems(FixedUpdate, run_wave_director.in_set(WaveSpawning));
+    }
+}
+
+fn load_waves(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WaveListHandle(asset_server.load(WAVES_PATH)));
+}
+
+fn apply_waves(
+    mut events: EventReader<AssetEvent<WaveList>>,
+    handle: Res<WaveListHandle>,
+    lists: Res<Assets<WaveList>>,
+    mut director: ResMut<WaveDirector>,
+) {
+    for event in events.read() {
+        match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } if *id == handle.0.id() => {
+                if let Some(list) = lists.get(*id) {
+                    info!("applied wave definitions from assets/{}", WAVES_PATH);
+                    director.set_list(list.clone());
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn run_wave_director(
+    time: Res<Time>,
+    mut director: ResMut<WaveDirector>,
+    mut rng: ResMut<GameRng>,
+    enemies: Query<(), With<WaveEnemy>>,
+    mut started: EventWriter<WaveStarted>,
+    mut spawn: EventWriter<SpawnEnemy>,
+) {
+    director.tick(
+        time.delta(),
+        enemies.iter().count(),
+        &mut *rng,
+        &mut |event| {
+            started.send(event);
+        },
+        &mut |order| {
+            spawn.send(SpawnEnemy(order));
+        },
+    );
+}
+
+// same as `config::config_settled`, for the wave definitions
+pub fn waves_settled(
+    handle: Option<Res<WaveListHandle>>,
+    asset_server: Res<AssetServer>,
+    lists: Res<Assets<WaveList>>,
+    director: Res<WaveDirector>,
+) -> bool {
+    let Some(handle) = handle else {
+        return false;
+    };
+    match asset_server.load_state(&handle.0) {
+        LoadState::Loaded => lists.get(&handle.0) == Some(&director.list),
+        LoadState::Failed => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    // ticks until `ticks` run out, returning the started waves and spawn orders
+    fn run(
+        director: &mut WaveDirector,
+        rng: &mut StdRng,
+        ticks: usize,
+        alive_enemies: usize,
+    ) -> (Vec<u32>, Vec<SpawnOrder>) {
+        let mut waves = Vec::new();
+        let mut orders = Vec::new();
+        for _ in 0..ticks {
+            director.tick(
+                STEP,
+                alive_enemies,
+                rng,
+                &mut |event| waves.push(event.number),
+                &mut |order| orders.push(order),
+            );
+        }
+        (waves, orders)
+    }
+
+    fn list() -> WaveList {
+        WaveList::from_bytes(
+            br#"(
+                delay_between_waves: 1.,
+                waves: [
+                    (groups: [(enemy: Rocket, count: 4, formation: Line, spawn_interval: 0.5)]),
+                    (groups: [(enemy: Rocket, count: 2, formation: Random, spawn_interval: 0.)]),
+                ],
+                escalation: (count: 2., hp: 1.5, speed: 1., spawn_interval: 1., shooting_chance: 0.),
+            )"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn shipped_waves_are_valid() {
+        WaveList::from_bytes(include_bytes!("../assets/waves.ron")).unwrap();
+    }
+
+    #[test]
+    fn negative_escalation_is_rejected() {
+        let mut list = list();
+        list.escalation.shooting_chance = -0.5;
+        assert!(list.validate().is_err());
+
+        list.escalation.shooting_chance = f32::NAN;
+        assert!(list.validate().is_err());
+    }
+
+    #[test]
+    fn wave_spawns_its_groups_over_time() {
+        let mut director = WaveDirector::new(list());
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // one second of delay, then 4 ships 0.5s apart
+        let (waves, orders) = run(&mut director, &mut rng, 10, 0);
+        assert_eq!(waves, vec![1]);
+        assert_eq!(orders.len(), 1);
+
+        let (_, orders) = run(&mut director, &mut rng, 15, 4);
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[2].offset, Vec2::new(0., 0.75));
+
+        // the next wave waits for the screen to clear
+        let (waves, _) = run(&mut director, &mut rng, 50, 1);
+        assert!(waves.is_empty());
+        let (waves, orders) = run(&mut director, &mut rng, 15, 0);
+        assert_eq!(waves, vec![2]);
+        assert_eq!(orders.len(), 2);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_waves() {
+        let mut a = WaveDirector::new(list());
+        let mut b = WaveDirector::new(list());
+
+        let runs = [
+            run(&mut a, &mut StdRng::seed_from_u64(3), 300, 0),
+            run(&mut b, &mut StdRng::seed_from_u64(3), 300, 0),
+        ];
+
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[test]
+    fn looping_the_list_escalates() {
+        let mut director = WaveDirector::new(list());
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let (waves, orders) = run(&mut director, &mut rng, 100, 0);
+
+        assert_eq!(waves, vec![1, 2, 3, 4]);
+        // waves 3 and 4 are waves 1 and 2 with twice the ships
+        assert_eq!(orders.len(), 4 + 2 + 8 + 4);
+        assert_eq!(orders.last().unwrap().hp_multiplier, 1.5);
+    }
+
+    #[test]
+    fn boss_comes_after_the_last_wave() {
+        let mut list = list();
+        list.boss = WaveList::from_bytes(include_bytes!("../assets/waves.ron"))
+            .unwrap()
+            .boss;
+        let mut director = WaveDirector::new(list);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut started = Vec::new();
+
+        for _ in 0..200 {
+            director.tick(
+                STEP,
+                0,
+                &mut rng,
+                &mut |event| started.push(event),
+                &mut |_| (),
+            );
+        }
+
+        // and the list doesn't start over after it
+        let boss: Vec<_> = started.iter().map(|wave| wave.boss).collect();
+        assert_eq!(boss, vec![false, false, true]);
+        assert_eq!(started[2].number, 3);
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+// how long a press of fire is kept while the weapon is still cooling down
+pub const FIRE_BUFFER: Duration = Duration::from_millis(150);
+// highest level a weapon can be upgraded to
+pub const MAX_LEVEL: u32 = 3;
+// distance between two shots fired side by side
+const SHOT_SPACING: f32 = 24.;
+// radians between two shots of a spread
+const SPREAD_STEP: f32 = 0.15;
+// radians between two homing missiles
+const HOMING_STEP: f32 = 0.4;
+
+// What the player's gun fires, picked up from power-ups
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeaponKind {
+    // one shot per level, side by side
+    #[default]
+    Blaster,
+    // a fan of 1 + 2 * level shots
+    Spread,
+    // one shot going through every enemy on its way, dealing level damage
+    Laser,
+    // one missile per level, turning towards the closest enemy
+    Homing,
+}
+
+impl WeaponKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            WeaponKind::Blaster => "blaster",
+            WeaponKind::Spread => "spread",
+            WeaponKind::Laser => "laser",
+            WeaponKind::Homing => "homing",
+        }
+    }
+}
+
+// Direction and offset from the ship of one shot in a volley
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shot {
+    pub offset: Vec2,
+    pub direction: Vec2,
+}
+
+// Cooldown, kind and level of the player's gun, ticking on its own
+//
+// Holding fire shoots every time the cooldown is over, a press shortly before that
+// is buffered instead of being dropped. Enemies fire through `patterns::Emitter`.
+#[derive(Component, Debug)]
+pub struct Weapon {
+    pub kind: WeaponKind,
+    // 1 to `MAX_LEVEL`
+    pub level: u32,
+    cooldown: Timer,
+    // time left for a press to still fire once the cooldown is over
+    buffered: Duration,
+}
+
+impl Weapon {
+    // ready to fire right away
+    pub fn new(cooldown: Duration) -> Self {
+        let mut timer = Timer::new(cooldown, TimerMode::Once);
+        timer.tick(cooldown);
+        Self {
+            kind: WeaponKind::Blaster,
+            level: 1,
+            cooldown: timer,
+            buffered: Duration::ZERO,
+        }
+    }
+
+    // picking up the kind already fired raises its level, another kind keeps the level
+    pub fn upgrade(&mut self, kind: WeaponKind) {
+        if self.kind == kind {
+            self.level = (self.level + 1).min(MAX_LEVEL);
+        } else {
+            self.kind = kind;
+        }
+    }
+
+    // shots fired at once, centered on the ship and going right
+    pub fn volley(&self) -> Vec<Shot> {
+        let level = self.level as usize;
+        // `count` values `step` apart, centered on 0
+        let centered = |count: usize, step: f32| {
+            (0..count).map(move |i| (i as f32 - (count - 1) as f32 / 2.) * step)
+        };
+
+        match self.kind {
+            WeaponKind::Blaster => centered(level, SHOT_SPACING)
+                .map(|y| Shot {
+                    offset: Vec2::new(0., y),
+                    direction: Vec2::X,
+                })
+                .collect(),
+            WeaponKind::Spread => centered(1 + 2 * level, SPREAD_STEP)
+                .map(|angle| Shot {
+                    offset: Vec2::ZERO,
+                    direction: Vec2::from_angle(angle),
+                })
+                .collect(),
+            WeaponKind::Laser => vec![Shot {
+                offset: Vec2::ZERO,
+                direction: Vec2::X,
+            }],
+            WeaponKind::Homing => centered(level, SHOT_SPACING)
+                .zip(centered(level, HOMING_STEP))
+                .map(|(y, angle)| Shot {
+                    offset: Vec2::new(0., y),
+                    direction: Vec2::from_angle(angle),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown.duration()
+    }
+
+    // a new cooldown takes effect from the next shot on
+    pub fn set_cooldown(&mut self, cooldown: Duration) {
+        self.cooldown.set_duration(cooldown);
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        self.cooldown.tick(delta);
+        self.buffered = self.buffered.saturating_sub(delta);
+    }
+
+    // called every tick fire is pressed or held
+    pub fn trigger(&mut self) {
+        self.buffered = FIRE_BUFFER;
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown.finished()
+    }
+
+    // true when a shot goes off, which restarts the cooldown
+    pub fn try_fire(&mut self) -> bool {
+        if !self.is_ready() || self.buffered.is_zero() {
+            return false;
+        }
+        self.cooldown.reset();
+        self.buffered = Duration::ZERO;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_millis(300);
+    const TICK: Duration = Duration::from_millis(50);
+
+    // fires for `ticks` ticks, calling `trigger` on the ticks `pressed` says
+    fn shots(weapon: &mut Weapon, ticks: usize, pressed: impl Fn(usize) -> bool) -> Vec<usize> {
+        (0..ticks)
+            .filter(|&tick| {
+                weapon.tick(TICK);
+                if pressed(tick) {
+                    weapon.trigger();
+                }
+                weapon.try_fire()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn holding_fire_shoots_at_the_cooldown_rate() {
+        let mut weapon = Weapon::new(COOLDOWN);
+
+        assert_eq!(shots(&mut weapon, 13, |_| true), vec![0, 6, 12]);
+    }
+
+    #[test]
+    fn a_press_during_the_cooldown_is_buffered() {
+        let mut weapon = Weapon::new(COOLDOWN);
+        assert_eq!(shots(&mut weapon, 1, |_| true), vec![0]);
+
+        // pressed 100ms before the cooldown is over
+        assert_eq!(shots(&mut weapon, 8, |tick| tick == 3), vec![5]);
+    }
+
+    #[test]
+    fn an_old_press_is_dropped() {
+        let mut weapon = Weapon::new(COOLDOWN);
+        assert_eq!(shots(&mut weapon, 1, |_| true), vec![0]);
+
+        // pressed right after the shot, long before the cooldown is over
+        assert!(shots(&mut weapon, 10, |tick| tick == 0).is_empty());
+    }
+
+    #[test]
+    fn the_same_kind_levels_up() {
+        let mut weapon = Weapon::new(COOLDOWN);
+        assert_eq!(weapon.volley().len(), 1);
+
+        weapon.upgrade(WeaponKind::Spread);
+        assert_eq!((weapon.kind, weapon.level), (WeaponKind::Spread, 1));
+        assert_eq!(weapon.volley().len(), 3);
+
+        for _ in 0..5 {
+            weapon.upgrade(WeaponKind::Spread);
+        }
+        assert_eq!(weapon.level, MAX_LEVEL);
+        let volley = weapon.volley();
+        assert_eq!(volley.len(), 7);
+        assert!((volley[0].direction.y + volley[6].direction.y).abs() < 1e-5);
+
+        weapon.upgrade(WeaponKind::Homing);
+        assert_eq!((weapon.kind, weapon.level), (WeaponKind::Homing, MAX_LEVEL));
+    }
+}
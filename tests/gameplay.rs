@@ -0,0 +1,383 @@
+use std::{env, fs, thread, time::Duration};
+
+use bevy::{app::AppExit, prelude::*, time::TimeUpdateStrategy};
+use rusty_invaders::{
+    boss::Boss,
+    enemy::Enemy,
+    health::{DamageEvent, Health, Invulnerable},
+    input::PlayerInput,
+    player::Player,
+    powerups::{PowerUp, PowerUpKind, SpawnPowerUp},
+    replay::{Playback, Recording, ReplayMode},
+    score::Score,
+    waves::{EnemyKind, SpawnEnemy, SpawnOrder, WaveStarted},
+    weapon::{Weapon, WeaponKind},
+    GamePlugin, GameState, HeadlessPlugin, PlayArea,
+};
+
+// one update is one simulation tick
+const FRAME: Duration = Duration::from_micros(15625);
+
+// starts a round and waits until the settings are loaded
+//
+// High scores and settings go to a temp folder named after the test, never to the
+// player's data folder.
+fn start(replay: ReplayMode) -> App {
+    start_with_frame(replay, FRAME)
+}
+
+fn start_with_frame(replay: ReplayMode, frame: Duration) -> App {
+    let test = thread::current()
+        .name()
+        .unwrap_or("test")
+        .replace("::", "-");
+    let dir = env::temp_dir().join(format!("rusty-invaders-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HeadlessPlugin,
+        GamePlugin {
+            seed: Some(42),
+            replay,
+            skip_menu: true,
+            data_dir: Some(dir),
+        },
+    ))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(frame));
+
+    for _ in 0..500 {
+        app.update();
+        if *app.world.resource::<State<GameState>>() == GameState::Playing {
+            return app;
+        }
+        // assets load on other threads
+        thread::sleep(Duration::from_millis(2));
+    }
+    panic!("the round never started");
+}
+
+fn run(app: &mut App, seconds: f32) {
+    for _ in 0..(seconds / FRAME.as_secs_f32()).round() as usize {
+        app.update();
+    }
+}
+
+fn rockets(app: &mut App) -> Vec<Vec3> {
+    app.world
+        .query_filtered::<&Transform, With<Enemy>>()
+        .iter(&app.world)
+        .map(|transform| transform.translation)
+        .collect()
+}
+
+fn powerups(app: &mut App) -> Vec<(Vec3, PowerUpKind)> {
+    app.world
+        .query::<(&Transform, &PowerUp)>()
+        .iter(&app.world)
+        .map(|(transform, powerup)| (transform.translation, powerup.0))
+        .collect()
+}
+
+fn player(app: &mut App) -> (Vec3, f32) {
+    let (transform, health) = app
+        .world
+        .query_filtered::<(&Transform, &Health), With<Player>>()
+        .single(&app.world);
+    (transform.translation, health.current)
+}
+
+// places a rocket at `position` on the next tick
+fn spawn_rocket_at(app: &mut App, position: Vec2) {
+    let order = rocket_order(app, position);
+    app.world.send_event(SpawnEnemy(order));
+}
+
+fn rocket_order(app: &App, position: Vec2) -> SpawnOrder {
+    let bounds = app.world.resource::<PlayArea>().bounds;
+    SpawnOrder {
+        enemy: EnemyKind::Rocket,
+        offset: Vec2::new(
+            position.x - bounds.max.x - 100.,
+            (position.y - bounds.center().y) / bounds.half_size().y,
+        ),
+        hp_multiplier: 1.,
+        speed_multiplier: 1.,
+        is_shooting: false,
+    }
+}
+
+#[test]
+fn first_wave_spawns_rockets() {
+    let mut app = start(ReplayMode::Off);
+    assert!(rockets(&mut app).is_empty());
+
+    run(&mut app, 5.);
+
+    assert!(!rockets(&mut app).is_empty());
+    assert_eq!(
+        *app.world.resource::<State<GameState>>(),
+        GameState::Playing
+    );
+}
+
+#[test]
+fn ramming_a_rocket_costs_one_hp_and_destroys_it() {
+    let mut app = start(ReplayMode::Off);
+    let (position, hp) = player(&mut app);
+
+    spawn_rocket_at(&mut app, position.truncate());
+    run(&mut app, 0.1);
+
+    assert_eq!(player(&mut app).1, hp - 1.);
+    assert!(rockets(&mut app).is_empty());
+    assert_eq!(app.world.resource::<Score>().kills, 1);
+}
+
+#[test]
+fn shooting_a_rocket_scores() {
+    let fire = PlayerInput {
+        fire: true,
+        ..default()
+    };
+    let mut app = start(ReplayMode::Play(Recording {
+        seed: 42,
+        inputs: vec![fire; 200],
+    }));
+    let (position, _) = player(&mut app);
+
+    // three shots to destroy it, right in front of the player
+    spawn_rocket_at(&mut app, position.truncate() + Vec2::new(600., 0.));
+    run(&mut app, 2.);
+
+    let score = app.world.resource::<Score>();
+    assert_eq!(score.kills, 1);
+    assert_eq!(score.points, 100);
+}
+
+#[test]
+fn weak_rockets_still_get_one_hp() {
+    let mut app = start(ReplayMode::Off);
+    let (position, _) = player(&mut app);
+
+    let order = rocket_order(&app, position.truncate() + Vec2::new(600., 0.));
+    app.world.send_event(SpawnEnemy(SpawnOrder {
+        hp_multiplier: 0.1,
+        ..order
+    }));
+    run(&mut app, 0.1);
+
+    let hp: Vec<f32> = app
+        .world
+        .query_filtered::<&Health, With<Enemy>>()
+        .iter(&app.world)
+        .map(|health| health.current)
+        .collect();
+    assert_eq!(hp, vec![1.]);
+}
+
+#[test]
+fn a_bomb_destroys_rockets_on_screen() {
+    let bomb = PlayerInput {
+        bomb: true,
+        ..default()
+    };
+    let mut inputs = vec![PlayerInput::default(); 30];
+    inputs[10] = bomb;
+    let mut app = start(ReplayMode::Play(Recording { seed: 42, inputs }));
+    let (position, _) = player(&mut app);
+
+    spawn_rocket_at(&mut app, position.truncate() + Vec2::new(600., 0.));
+    run(&mut app, 0.3);
+
+    assert_eq!(app.world.resource::<Score>().kills, 1);
+}
+
+#[test]
+fn the_player_respawns_until_the_lives_run_out() {
+    let mut app = start(ReplayMode::Off);
+    let (start_position, hp) = player(&mut app);
+    let player_entity = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    let kill = |app: &mut App| {
+        app.world.send_event(DamageEvent {
+            target: player_entity,
+            amount: hp,
+            source: player_entity,
+        });
+        run(app, 0.1);
+    };
+
+    app.world
+        .entity_mut(player_entity)
+        .get_mut::<Transform>()
+        .unwrap()
+        .translation
+        .y += 200.;
+    kill(&mut app);
+    assert_eq!(player(&mut app), (start_position, hp));
+    assert_eq!(app.world.get::<Player>(player_entity).unwrap().lives(), 2);
+    // hits right after respawning are ignored
+    kill(&mut app);
+    assert_eq!(app.world.get::<Player>(player_entity).unwrap().lives(), 2);
+
+    for _ in 0..2 {
+        app.world.entity_mut(player_entity).remove::<Invulnerable>();
+        kill(&mut app);
+    }
+    assert_eq!(
+        *app.world.resource::<State<GameState>>(),
+        GameState::GameOver
+    );
+}
+
+#[test]
+fn flying_into_power_ups_collects_them() {
+    let mut app = start(ReplayMode::Off);
+    let (position, hp) = player(&mut app);
+    let player_entity = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    app.world.send_event(DamageEvent {
+        target: player_entity,
+        amount: 3.,
+        source: player_entity,
+    });
+    run(&mut app, 0.1);
+
+    for kind in [PowerUpKind::Heal, PowerUpKind::Spread, PowerUpKind::Spread] {
+        app.world.send_event(SpawnPowerUp {
+            kind,
+            position: position.truncate(),
+        });
+        run(&mut app, 0.1);
+    }
+
+    assert_eq!(player(&mut app).1, hp - 1.);
+    let weapon = app.world.query::<&Weapon>().single(&app.world);
+    assert_eq!((weapon.kind, weapon.level), (WeaponKind::Spread, 2));
+}
+
+#[test]
+fn beating_the_boss_wins() {
+    let mut app = start(ReplayMode::Off);
+    app.world.send_event(WaveStarted {
+        number: 5,
+        boss: true,
+    });
+    run(&mut app, 0.1);
+
+    let (boss, hp) = app
+        .world
+        .query_filtered::<(Entity, &Health), With<Boss>>()
+        .single(&app.world);
+    let hp = hp.max;
+    let player = app
+        .world
+        .query_filtered::<Entity, With<Player>>()
+        .single(&app.world);
+    app.world.send_event(DamageEvent {
+        target: boss,
+        amount: hp,
+        source: player,
+    });
+    run(&mut app, 0.1);
+
+    assert_eq!(
+        *app.world.resource::<State<GameState>>(),
+        GameState::Victory
+    );
+}
+
+#[test]
+fn same_seed_and_input_play_out_the_same() {
+    let up_and_fire = PlayerInput {
+        fire: true,
+        ..PlayerInput::moving(Vec2::new(0.3, 1.))
+    };
+    let recording = Recording {
+        seed: 7,
+        inputs: [PlayerInput::default(), up_and_fire].repeat(300),
+    };
+    let mut a = start(ReplayMode::Play(recording.clone()));
+    let mut b = start(ReplayMode::Play(recording));
+
+    run(&mut a, 10.);
+    run(&mut b, 10.);
+
+    assert_eq!(rockets(&mut a), rockets(&mut b));
+    assert_eq!(player(&mut a), player(&mut b));
+}
+
+#[test]
+fn the_frame_rate_does_not_change_how_a_round_plays_out() {
+    let up_and_fire = PlayerInput {
+        fire: true,
+        ..PlayerInput::moving(Vec2::new(0.3, 1.))
+    };
+    let mut inputs = [PlayerInput::default(), up_and_fire].repeat(600);
+    // a bomb kills many ships in one tick, each rolling for a power-up in turn
+    for tick in [500, 800, 1100] {
+        inputs[tick].bomb = true;
+    }
+    let recording = Recording { seed: 7, inputs };
+    let ticks = |app: &App| app.world.resource::<Playback>().ticks();
+    let mut a = start(ReplayMode::Play(recording.clone()));
+    // three ticks per frame, whatever runs once a frame sees different times
+    let mut b = start_with_frame(ReplayMode::Play(recording), FRAME * 3);
+
+    for _ in 0..390 {
+        b.update();
+    }
+    while ticks(&a) < ticks(&b) {
+        a.update();
+    }
+
+    assert_eq!(ticks(&a), ticks(&b));
+    assert_eq!(rockets(&mut a), rockets(&mut b));
+    assert_eq!(player(&mut a), player(&mut b));
+    let score = |app: &App| {
+        let score = app.world.resource::<Score>();
+        (score.points, score.kills)
+    };
+    assert_eq!(score(&a), score(&b));
+    assert_eq!(powerups(&mut a), powerups(&mut b));
+    assert!(score(&a).1 > 0);
+}
+
+#[test]
+fn quitting_in_the_middle_of_a_round_keeps_the_recording() {
+    let path = env::temp_dir().join(format!("rusty-invaders-quit-{}.replay", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let mut app = start(ReplayMode::Record(path.clone()));
+
+    run(&mut app, 0.5);
+    app.world.send_event(AppExit);
+    app.update();
+
+    let recording = Recording::load_from(&path).unwrap();
+    assert_eq!(recording.seed, 42);
+    assert!(recording.inputs.len() >= 32);
+}
+
+#[test]
+fn playback_quits_once_the_input_runs_out() {
+    let mut app = start(ReplayMode::Play(Recording {
+        seed: 42,
+        inputs: vec![PlayerInput::default(); 10],
+    }));
+    let exited = |app: &App| !app.world.resource::<Events<AppExit>>().is_empty();
+
+    run(&mut app, 0.1);
+    assert!(!exited(&app));
+    let mut frames = 0;
+    while !exited(&app) && frames < 20 {
+        app.update();
+        frames += 1;
+    }
+    assert!(exited(&app));
+}